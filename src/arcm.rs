@@ -1,125 +1,1208 @@
-use std::fmt::Debug;
-use std::sync::{Arc, Mutex, Weak};
+use crate::arcmo::Arcmo;
+use crate::derived::Derived;
+use crate::error::PoisonError;
+#[cfg(feature = "std")]
+use crate::error::{LockError, TimedOut};
+use crate::projection::WeakProjection;
+use crate::sync::{
+    fmt_debug, get_mut, into_inner, is_poisoned, lock, lock_checked, lock_traced, try_lock,
+    Generation, Held, Mutex, MutexGuard, Owner, Span,
+};
+#[cfg(feature = "std")]
+use crate::sync::{lock_timeout, poll_until, try_lock_checked};
+#[cfg(not(feature = "spin"))]
+use crate::sync::{wait_while, Condvar};
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::Ordering as CmpOrdering;
+use core::fmt::{Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{AddAssign, Deref, DerefMut, DivAssign, MulAssign, RemAssign, SubAssign};
+#[cfg(feature = "std")]
+use std::time::Duration;
 
 /// A wrapper combining Arc and Mutex for convenient shared mutable access
-/// Only works with types that implement Clone
-pub struct Arcm<T: Clone> {
+/// Reading the value out with `value` requires `T: Clone`; modifying it in
+/// place and borrowing it with `with` work for any type. A resource that must
+/// never be copied, such as a connection, can be shared as an Arcm as is; the
+/// copying methods are simply unavailable for it.
+///
+/// Like `Arc<Mutex<T>>`, an Arcm is `Send` and `Sync` only when `T: Send`.
+/// An `Arcm<Rc<_>>` still works, but stays on the thread that created it;
+/// `Arcm::spawn_with` rejects it at the call.
+pub struct Arcm<T> {
     inner: Arc<Mutex<T>>,
+    shared: Arc<Shared<T>>,
 }
 
-impl<T: Clone> Arcm<T> {
+/// Identifies a callback registered with [`Arcm::subscribe`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// A copy of an Arcm's value taken by [`Arcm::snapshot`], tagged with the
+/// generation it was taken at
+#[derive(Clone, Debug)]
+pub struct Snapshot<T> {
+    value: T,
+    generation: u64,
+}
+
+impl<T> Snapshot<T> {
+    /// Returns the captured value
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the [`Arcm::generation`] at the time of capture
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Consumes the snapshot, returning the captured value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+type Subscriber<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+struct Subscribers<T> {
+    next_id: u64,
+    // Swapped wholesale on (un)subscribe so notifying only needs to clone the Arc
+    list: Arc<Vec<(SubscriptionId, Subscriber<T>)>>,
+}
+
+/// State shared by every clone of an Arcm alongside the value itself
+struct Shared<T> {
+    subscribers: Mutex<Subscribers<T>>,
+    generation: Generation,
+    #[cfg(not(feature = "spin"))]
+    condvar: Condvar,
+    owner: Owner,
+}
+
+impl<T> Shared<T> {
+    fn new() -> Self {
+        Self {
+            subscribers: Mutex::new(Subscribers {
+                next_id: 0,
+                list: Arc::new(Vec::new()),
+            }),
+            generation: Generation::new(),
+            #[cfg(not(feature = "spin"))]
+            condvar: Condvar::new(),
+            owner: Owner::new(),
+        }
+    }
+
+    /// Bumps the generation, wakes waiters and notifies subscribers of the new
+    /// value, called while the value lock is held
+    fn changed(&self, value: &T) {
+        self.generation.bump();
+        #[cfg(not(feature = "spin"))]
+        self.condvar.notify_all();
+        let subscribers = Arc::clone(&lock(&self.subscribers).list);
+        for (_, subscriber) in subscribers.iter() {
+            subscriber(value);
+        }
+    }
+}
+
+impl<T> Arcm<T> {
     /// Creates a new Arcm containing the given value
     pub fn new(value: T) -> Self {
         Self {
             inner: Arc::new(Mutex::new(value)),
+            shared: Arc::new(Shared::new()),
         }
     }
 
+    /// Creates a new Arcm from a fallible initializer, forwarding its error
+    pub fn try_new<F, E>(f: F) -> Result<Self, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        f().map(Self::new)
+    }
+
+    /// Creates a new Arcm whose value can hold a weak reference to itself,
+    /// like `Arc::new_cyclic`. The weak reference can't be upgraded until
+    /// `f` has returned.
+    pub fn new_cyclic<F>(f: F) -> Self
+    where
+        F: FnOnce(&WeakArcm<T>) -> T,
+    {
+        let shared = Arc::new(Shared::new());
+        let inner = Arc::new_cyclic(|inner| {
+            let weak = WeakArcm {
+                inner: inner.clone(),
+                shared: Arc::downgrade(&shared),
+            };
+            Mutex::new(f(&weak))
+        });
+        Self { inner, shared }
+    }
+
+    /// Wraps an existing `Arc<Mutex<T>>`, sharing its value with every other
+    /// holder of that Arc. The mutex type depends on the enabled features;
+    /// see [`Mutex`](crate::Mutex).
+    ///
+    /// Subscriptions and the generation counter belong to the wrapper, so
+    /// handles adopted separately from the same Arc don't share them, and
+    /// changes made directly through the raw mutex aren't tracked.
+    pub fn from_arc(inner: Arc<crate::Mutex<T>>) -> Self {
+        Self {
+            inner,
+            shared: Arc::new(Shared::new()),
+        }
+    }
+
+    /// Returns a clone of the underlying `Arc<Mutex<T>>` for interop with APIs
+    /// that expect the raw primitive. The mutex type depends on the enabled
+    /// features; see [`Mutex`](crate::Mutex).
+    pub fn as_arc(&self) -> Arc<crate::Mutex<T>> {
+        Arc::clone(&self.inner)
+    }
+
     /// Modifies the contained value using the provided closure
     pub fn modify<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&mut T) -> R,
     {
-        let mut guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
-        f(&mut *guard)
+        let (_span, mut guard) = self.acquire_traced("Arcm::modify");
+        let result = f(&mut *guard);
+        self.shared.changed(&guard);
+        result
     }
 
-    /// Returns a copy of the contained value
-    pub fn value(&self) -> T {
-        match self.inner.lock() {
-            Ok(guard) => guard.clone(),
-            Err(poisoned) => poisoned.into_inner().clone(),
+    /// Locks the value and returns a guard for several operations under a
+    /// single lock acquisition. The lock is released when the guard is dropped.
+    ///
+    /// If the value was mutably borrowed through the guard, dropping it counts
+    /// as one modification: subscribers are notified and the generation bumps.
+    pub fn lock(&self) -> ArcmGuard<'_, T> {
+        ArcmGuard::new(self.acquire(), &self.shared)
+    }
+
+    /// Like [`Arcm::lock`], but gives up with [`LockError::Timeout`] if the
+    /// lock can't be taken within `timeout`. Retries with exponential backoff
+    /// rather than blocking. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn lock_or_timeout(&self, timeout: Duration) -> Result<ArcmGuard<'_, T>, LockError> {
+        let guard = lock_timeout(timeout, || Ok(try_lock(&self.inner)))?;
+        Ok(ArcmGuard::new(self.locked(guard), &self.shared))
+    }
+
+    /// Modifies the value like [`Arcm::modify`], but gives up with
+    /// [`TimedOut`] if the lock can't be taken within `timeout`. Requires the
+    /// `std` feature.
+    #[cfg(feature = "std")]
+    pub fn with_timeout<F, R>(&self, timeout: Duration, f: F) -> Result<R, TimedOut>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let guard = lock_timeout(timeout, || Ok(try_lock(&self.inner))).map_err(|_| TimedOut)?;
+        let mut guard = self.locked(guard);
+        let result = f(&mut *guard);
+        self.shared.changed(&guard);
+        Ok(result)
+    }
+
+    /// Like [`Arcm::lock_or_timeout`], but returns [`LockError::Poisoned`]
+    /// instead of recovering if the lock was poisoned
+    #[cfg(feature = "std")]
+    pub fn lock_or_timeout_checked(
+        &self,
+        timeout: Duration,
+    ) -> Result<ArcmGuard<'_, T>, LockError> {
+        let guard = lock_timeout(timeout, || try_lock_checked(&self.inner))?;
+        Ok(ArcmGuard::new(self.locked(guard), &self.shared))
+    }
+
+    /// Like [`Arcm::lock`], but the guard owns a clone of this handle instead
+    /// of borrowing it, so it can be stored or outlive `self`
+    pub fn lock_owned(&self) -> OwnedArcmGuard<T>
+    where
+        T: 'static,
+    {
+        let arcm = self.clone();
+        let locked = arcm.acquire();
+        // SAFETY: the guard borrows the mutex and owner inside the Arc
+        // allocations, not `arcm` itself, so moving `arcm` doesn't invalidate
+        // it. OwnedArcmGuard keeps `arcm` alive for as long as it holds the
+        // guard and drops the guard first.
+        let locked = unsafe { core::mem::transmute::<Locked<'_, T>, Locked<'static, T>>(locked) };
+        OwnedArcmGuard {
+            locked,
+            arcm,
+            dirty: false,
+            _not_send: PhantomData,
         }
     }
 
+    /// Returns a new strong handle together with an owned guard over the same
+    /// value, so a new owner can start with the lock already held. Like any
+    /// guard, it must be dropped on the thread that locked.
+    pub fn clone_and_lock(&self) -> (Arcm<T>, OwnedArcmGuard<T>)
+    where
+        T: 'static,
+    {
+        (self.clone(), self.lock_owned())
+    }
+
+    /// Runs the provided closure with a reference to the contained value,
+    /// without cloning it
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.acquire())
+    }
+
+    /// Runs `f` with a reference to the contained value and returns `self`
+    /// for chaining. The lock is released before this returns.
+    pub fn inspect<F>(&self, f: F) -> &Self
+    where
+        F: FnOnce(&T),
+    {
+        f(&self.acquire());
+        self
+    }
+
     /// Returns a weak reference to the contained value
     pub fn downgrade(&self) -> WeakArcm<T> {
         WeakArcm {
             inner: Arc::downgrade(&self.inner),
+            shared: Arc::downgrade(&self.shared),
         }
     }
 
+    /// Same as [`Arcm::downgrade`], named to read like `clone` at call sites
+    /// that should only hold a weak reference
+    pub fn weak_clone(&self) -> WeakArcm<T> {
+        self.downgrade()
+    }
+
     /// Replace the value without cloning the old one, returns the old value.
     pub fn replace(&self, value: T) -> T {
-        let mut guard = match self.inner.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => poisoned.into_inner(),
+        let (_span, mut guard) = self.acquire_traced("Arcm::replace");
+        let old = core::mem::replace(&mut *guard, value);
+        self.shared.changed(&guard);
+        old
+    }
+
+    /// Swaps `temp` in and returns a guard that swaps the original value back
+    /// when dropped, including during a panic
+    pub fn scoped_replace(&self, temp: T) -> ScopeGuard<T> {
+        ScopeGuard {
+            original: Some(self.replace(temp)),
+            arcm: self.clone(),
+        }
+    }
+
+    /// Replaces the value and runs `f` with the old one while still holding
+    /// the lock, so handling the old value is serialized against later swaps.
+    /// Returns the result of `f`.
+    pub fn swap_with<F, R>(&self, value: T, f: F) -> R
+    where
+        F: FnOnce(T) -> R,
+    {
+        let mut guard = self.acquire();
+        let old = core::mem::replace(&mut *guard, value);
+        self.shared.changed(&guard);
+        f(old)
+    }
+
+    /// Overwrites the value, dropping the old one
+    pub fn set(&self, value: T) {
+        let mut guard = self.acquire();
+        *guard = value;
+        self.shared.changed(&guard);
+    }
+
+    /// Returns a mutable reference to the value without locking, if this is
+    /// the only handle to it and no weak references exist.
+    ///
+    /// Changes made this way bump the generation but don't notify subscribers.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let mutex = Arc::get_mut(&mut self.inner)?;
+        self.shared.generation.bump();
+        Some(get_mut(mutex))
+    }
+
+    /// Replaces the value with `new` only if it currently equals `current`.
+    /// The check and the swap happen under a single lock acquisition.
+    /// Returns true if the value was replaced.
+    pub fn compare_and_set(&self, current: &T, new: T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut guard = self.acquire();
+        if *guard == *current {
+            *guard = new;
+            self.shared.changed(&guard);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns true if both handles hold equal values. Locks both in a
+    /// consistent order, or once if they share an allocation.
+    ///
+    /// The `PartialEq` impl, kept for keys that are never mutated, delegates
+    /// here. Call this instead when a type holding an Arcm should compare it
+    /// by value without deriving `PartialEq`, `Eq` and `Hash` itself and so
+    /// becoming usable as a map key.
+    pub fn value_eq(&self, other: &Arcm<T>) -> bool
+    where
+        T: PartialEq,
+    {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            let value = self.acquire();
+            return PartialEq::eq(&*value, &*value);
+        }
+        let (a, b) = Arcm::acquire_pair(self, other);
+        *a == *b
+    }
+
+    /// Returns true if the contained value equals `other`, comparing in place
+    /// without cloning
+    pub fn value_eq_to(&self, other: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        *self.acquire() == *other
+    }
+
+    /// Replaces the value only if the generation is still `expected`, for
+    /// optimistic updates: read the value and generation, compute, then commit
+    /// and retry on conflict. Returns `Ok` with the new generation, or `Err`
+    /// with the current one if another change got there first.
+    pub fn replace_if_generation(&self, expected: u64, value: T) -> Result<u64, u64> {
+        let mut guard = self.acquire();
+        let current = self.generation();
+        if current != expected {
+            return Err(current);
+        }
+        *guard = value;
+        self.shared.changed(&guard);
+        Ok(self.generation())
+    }
+
+    /// Sets the value only if it differs from the current one. Returns true if
+    /// it was written; equal writes leave the generation alone and don't
+    /// notify subscribers.
+    pub fn set_if_changed(&self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut guard = self.acquire();
+        if *guard == value {
+            return false;
+        }
+        *guard = value;
+        self.shared.changed(&guard);
+        true
+    }
+
+    /// Overwrites the value with a snapshot taken earlier. Returns false if
+    /// the value had been modified since the snapshot was taken; it is
+    /// restored either way.
+    pub fn restore(&self, snapshot: Snapshot<T>) -> bool {
+        let mut guard = self.acquire();
+        let unchanged = self.generation() == snapshot.generation;
+        *guard = snapshot.value;
+        self.shared.changed(&guard);
+        unchanged
+    }
+
+    /// Returns true if the value differs from `previous` according to `eq`,
+    /// which is called as `eq(previous, current)` under the lock. For types
+    /// without a suitable `PartialEq`, such as floats compared with a tolerance.
+    pub fn value_changed_since<F>(&self, previous: &T, eq: F) -> bool
+    where
+        F: FnOnce(&T, &T) -> bool,
+    {
+        !eq(previous, &self.acquire())
+    }
+
+    /// Adds `by` to the value under the lock and returns the new value
+    pub fn increment(&self, by: T) -> T
+    where
+        T: AddAssign + Copy,
+    {
+        let mut guard = self.acquire();
+        *guard += by;
+        self.shared.changed(&guard);
+        *guard
+    }
+
+    /// Subtracts `by` from the value under the lock and returns the new value
+    pub fn decrement(&self, by: T) -> T
+    where
+        T: SubAssign + Copy,
+    {
+        let mut guard = self.acquire();
+        *guard -= by;
+        self.shared.changed(&guard);
+        *guard
+    }
+
+    /// Like [`Arcm::modify`], but returns an error instead of recovering if
+    /// the lock was poisoned by a panicking thread. The closure is not run in
+    /// that case.
+    pub fn try_modify_checked<F, R>(&self, f: F) -> Result<R, PoisonError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = self.acquire_checked()?;
+        let result = f(&mut *guard);
+        self.shared.changed(&guard);
+        Ok(result)
+    }
+
+    /// Like [`Arcm::replace`], but returns an error if the lock was poisoned.
+    /// The new value is dropped in that case.
+    pub fn replace_checked(&self, value: T) -> Result<T, PoisonError> {
+        let mut guard = self.acquire_checked()?;
+        let old = core::mem::replace(&mut *guard, value);
+        self.shared.changed(&guard);
+        Ok(old)
+    }
+
+    /// Blocks until `predicate` returns true for the value, then runs `then`
+    /// under the same lock and returns its result.
+    ///
+    /// Waiters re-check the predicate after every modification made through
+    /// any handle, or when [`Arcm::notify_all`] is called. Not available with
+    /// the `spin` feature.
+    #[cfg(not(feature = "spin"))]
+    pub fn wait_until<F, R>(&self, mut predicate: F, then: impl FnOnce(&mut T) -> R) -> R
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut guard = self.acquire();
+        guard.guard = wait_while(&self.shared.condvar, guard.guard, |value| {
+            // Other threads may have held the lock while this one waited
+            self.shared.owner.claim();
+            !predicate(value)
+        });
+        let result = then(&mut guard);
+        self.shared.changed(&guard);
+        result
+    }
+
+    /// Wakes every thread blocked in [`Arcm::wait_until`] so they re-check
+    /// their predicates. Modifications already do this automatically; call it
+    /// when a predicate depends on state outside the Arcm, after updating that
+    /// state while holding the lock (e.g. inside [`Arcm::with`]).
+    #[cfg(not(feature = "spin"))]
+    pub fn notify_all(&self) {
+        self.shared.condvar.notify_all();
+    }
+
+    /// Spawns a thread that runs `f` with a clone of this handle. Requires the
+    /// `std` feature.
+    #[cfg(feature = "std")]
+    pub fn spawn_with<F>(&self, f: F) -> std::thread::JoinHandle<()>
+    where
+        F: FnOnce(Arcm<T>) + Send + 'static,
+        T: Send + 'static,
+    {
+        let handle = self.clone();
+        std::thread::spawn(move || f(handle))
+    }
+
+    /// Returns true if a thread ever panicked while holding the lock. The
+    /// value is still recovered transparently; this only reports that it
+    /// happened. Always false with the `parking_lot` and `spin` features.
+    pub fn is_poisoned(&self) -> bool {
+        is_poisoned(&self.inner)
+    }
+
+    /// Poisons the lock, as if a thread had panicked while holding it, so code
+    /// handling poisoning can be tested. A no-op with the `parking_lot` and
+    /// `spin` features, which never poison. Requires the `test-util` feature.
+    #[cfg(all(feature = "std", any(test, feature = "test-util")))]
+    pub fn force_poison(&self)
+    where
+        T: Send,
+    {
+        crate::sync::force_poison(&self.inner);
+    }
+
+    /// Returns the number of modifications made so far through any handle.
+    /// The counter is bumped under the same lock as the mutation, so a changed
+    /// generation reliably means the value has been modified since it was read.
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.get()
+    }
+
+    /// Registers a callback that is invoked with the new value after every
+    /// modification made through this or any cloned handle.
+    ///
+    /// Callbacks run while the lock is still held, so they must not call back
+    /// into `modify`, `value`, `replace`, etc. on the same Arcm; doing so will
+    /// deadlock (debug builds panic instead). Subscribing and unsubscribing
+    /// from within a callback is fine.
+    pub fn subscribe<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        let mut subscribers = lock(&self.shared.subscribers);
+        let id = SubscriptionId(subscribers.next_id);
+        subscribers.next_id += 1;
+        let mut list = Vec::clone(&subscribers.list);
+        list.push((id, Arc::new(f)));
+        subscribers.list = Arc::new(list);
+        id
+    }
+
+    /// Removes a callback registered with [`Arcm::subscribe`]
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        let mut subscribers = lock(&self.shared.subscribers);
+        let mut list = Vec::clone(&subscribers.list);
+        list.retain(|(existing, _)| *existing != id);
+        subscribers.list = Arc::new(list);
+    }
+
+    /// Returns a weak handle to a shared value nested inside this one, found
+    /// by `f` on each access. See [`WeakProjection`] for how it resolves.
+    pub fn project_weak<U, F>(&self, f: F) -> WeakProjection<U>
+    where
+        T: Send + 'static,
+        F: Fn(&T) -> &Arc<crate::Mutex<U>> + Send + Sync + 'static,
+    {
+        WeakProjection::new(self.downgrade(), f)
+    }
+
+    /// Returns a live view holding `f` applied to the value, recomputed after
+    /// every modification until the [`Derived`] is dropped.
+    ///
+    /// `f` runs as a subscriber, while this Arcm is locked, so the same
+    /// restrictions as [`Arcm::subscribe`] apply.
+    pub fn derive<U, F>(&self, f: F) -> Derived<U>
+    where
+        T: Send + 'static,
+        U: Send + 'static,
+        F: Fn(&T) -> U + Send + Sync + 'static,
+    {
+        // Subscribing under the lock means no modification can slip in
+        // between computing the initial value and the first update
+        let value = self.acquire();
+        let target = Arcm::new(f(&value));
+        let updated = target.clone();
+        let id = self.subscribe(move |value| updated.set(f(value)));
+        drop(value);
+
+        let source = self.downgrade();
+        Derived::new(
+            target,
+            Box::new(move || {
+                if let Some(source) = source.upgrade() {
+                    source.unsubscribe(id);
+                }
+            }),
+        )
+    }
+}
+
+impl<T: Clone> Arcm<T> {
+    /// Returns a copy of the contained value
+    pub fn value(&self) -> T {
+        self.acquire().clone()
+    }
+
+    /// Runs `f` with a copy of the value taken under the lock. The lock is
+    /// released before `f` runs, so slow work such as notifying observers
+    /// doesn't block other handles, and `f` may use this Arcm freely.
+    pub fn with_snapshot<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(T) -> R,
+    {
+        f(self.value())
+    }
+
+    /// Returns a copy of the contained value without blocking, or None if
+    /// the lock is currently held elsewhere
+    pub fn try_value(&self) -> Option<T> {
+        self.try_acquire().map(|value| value.clone())
+    }
+
+    /// Copies the contained value into `target` with `clone_from`, letting it
+    /// reuse `target`'s existing allocation instead of creating a new one
+    pub fn clone_value_into(&self, target: &mut T) {
+        target.clone_from(&self.acquire());
+    }
+
+    /// Returns a mutable reference to the value, first cloning it into a new
+    /// allocation owned solely by this handle if it is currently shared, like
+    /// `Arc::make_mut`. The clone is taken under the lock, so it is a
+    /// consistent snapshot.
+    ///
+    /// Once forked, this handle no longer shares its value, subscribers or
+    /// generation with its former clones and weak references.
+    pub fn make_mut(&mut self) -> &mut T {
+        if Arc::get_mut(&mut self.inner).is_none() {
+            let value = self.acquire().clone();
+            *self = Arcm::new(value);
+        }
+        self.get_mut()
+            .expect("a freshly forked Arcm is uniquely owned")
+    }
+
+    /// Converts into an Arcmo holding a snapshot of the current value.
+    ///
+    /// The result is a new, independent allocation: changes made through it
+    /// are not seen by other clones of this Arcm, and vice versa.
+    pub fn into_arcmo(self) -> Arcmo<T> {
+        Arcmo::some(self.value())
+    }
+
+    /// Moves the value out without cloning if this is the only strong
+    /// handle, otherwise returns a copy. Weak references don't prevent the
+    /// move; they simply can't upgrade afterwards.
+    pub fn into_inner_or_clone(self) -> T {
+        match Arc::try_unwrap(self.inner) {
+            Ok(mutex) => into_inner(mutex),
+            Err(inner) => Arcm {
+                inner,
+                shared: self.shared,
+            }
+            .value(),
+        }
+    }
+
+    /// Converts into a new Arcm holding `f` applied to the value. The value
+    /// is moved into `f` without cloning if this is the only strong handle,
+    /// as with [`Arcm::into_inner_or_clone`].
+    ///
+    /// The result is a fresh allocation with its own subscribers and
+    /// generation; other clones of this Arcm keep the old value.
+    pub fn map_in_place<U, F>(self, f: F) -> Arcm<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Arcm::new(f(self.into_inner_or_clone()))
+    }
+
+    /// Runs a fallible modification against the contained value.
+    /// On `Ok` the changes are kept; on `Err` the value is rolled back to what
+    /// it was before the closure ran, so partial changes are never observed.
+    pub fn fetch_update<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut T) -> Result<R, E>,
+    {
+        let mut guard = self.acquire();
+        let original = guard.clone();
+        let result = f(&mut *guard);
+        match result {
+            Ok(_) => self.shared.changed(&guard),
+            Err(_) => *guard = original,
+        }
+        result
+    }
+
+    /// Like [`Arcm::value`], but returns an error if the lock was poisoned
+    pub fn value_checked(&self) -> Result<T, PoisonError> {
+        Ok(self.acquire_checked()?.clone())
+    }
+
+    /// Applies `f` and returns copies of the value from just before and just
+    /// after, both taken under the same lock acquisition
+    pub fn update<F>(&self, f: F) -> (T, T)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut guard = self.acquire();
+        let before = guard.clone();
+        f(&mut guard);
+        self.shared.changed(&guard);
+        (before, guard.clone())
+    }
+
+    /// Copies the value of `other` into this Arcm, with both locked, so every
+    /// clone of this handle sees it.
+    ///
+    /// Clones of a handle share one allocation that can't be swapped out from
+    /// under them, so installing new state means copying it in like this; the
+    /// two Arcms stay independent afterwards.
+    pub fn overwrite_from(&self, other: &Arcm<T>) {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return;
+        }
+        let (mut target, source) = Arcm::acquire_pair(self, other);
+        target.clone_from(&source);
+        self.shared.changed(&target);
+    }
+
+    /// Captures a copy of the current value together with its generation,
+    /// for handing back to [`Arcm::restore`] later
+    pub fn snapshot(&self) -> Snapshot<T> {
+        let guard = self.acquire();
+        Snapshot {
+            value: guard.clone(),
+            generation: self.generation(),
+        }
+    }
+}
+
+/// A held lock on an Arcm's value. In debug builds it also records the owning
+/// thread so reentrant locking panics instead of deadlocking.
+pub(crate) struct Locked<'a, T> {
+    // Declared first so the owner is cleared before the mutex unlocks
+    _held: Held<'a>,
+    guard: MutexGuard<'a, T>,
+}
+
+impl<T> Deref for Locked<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for Locked<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Arcm<T> {
+    /// Locks the value, recovering from poisoning. Panics in debug builds if
+    /// this thread already holds the lock, which would otherwise deadlock.
+    fn acquire(&self) -> Locked<'_, T> {
+        self.shared.owner.check("Arcm");
+        let guard = lock(&self.inner);
+        Locked {
+            _held: self.shared.owner.enter(),
+            guard,
+        }
+    }
+
+    /// Like [`Arcm::acquire`], but with the `tracing` feature the lock is
+    /// taken inside a span for `op`, which stays entered until it is dropped.
+    /// The span is declared first so it closes after the guard unlocks.
+    fn acquire_traced(&self, op: &'static str) -> (Span, Locked<'_, T>) {
+        self.shared.owner.check("Arcm");
+        let (span, guard) = lock_traced(&self.inner, op);
+        let locked = Locked {
+            _held: self.shared.owner.enter(),
+            guard,
         };
-        std::mem::replace(&mut *guard, value)
+        (span, locked)
+    }
+
+    /// Like [`Arcm::acquire`], but reports poisoning
+    fn acquire_checked(&self) -> Result<Locked<'_, T>, PoisonError> {
+        self.shared.owner.check("Arcm");
+        let guard = lock_checked(&self.inner)?;
+        Ok(Locked {
+            _held: self.shared.owner.enter(),
+            guard,
+        })
+    }
+
+    /// Locks the value if it is free, recovering from poisoning. A lock
+    /// already held by this thread just reports as contended.
+    fn try_acquire(&self) -> Option<Locked<'_, T>> {
+        try_lock(&self.inner).map(|guard| self.locked(guard))
+    }
+
+    /// Records this thread as the owner of a guard taken without blocking
+    fn locked<'a>(&'a self, guard: MutexGuard<'a, T>) -> Locked<'a, T> {
+        Locked {
+            _held: self.shared.owner.enter(),
+            guard,
+        }
+    }
+
+    /// Locks two handles to different allocations in address order, so
+    /// concurrent calls with the arguments swapped can't deadlock. The guards
+    /// are returned in argument order.
+    fn acquire_pair<'a>(a: &'a Self, b: &'a Self) -> (Locked<'a, T>, Locked<'a, T>) {
+        debug_assert!(!Arc::ptr_eq(&a.inner, &b.inner));
+        if a.addr() < b.addr() {
+            let a = a.acquire();
+            (a, b.acquire())
+        } else {
+            let b = b.acquire();
+            (a.acquire(), b)
+        }
+    }
+}
+
+/// A held lock on an Arcm's value, returned by [`Arcm::lock`]
+pub struct ArcmGuard<'a, T> {
+    locked: Locked<'a, T>,
+    shared: &'a Shared<T>,
+    dirty: bool,
+    // Like MutexGuard, the lock must be released on the thread that took it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a, T> ArcmGuard<'a, T> {
+    fn new(locked: Locked<'a, T>, shared: &'a Shared<T>) -> Self {
+        Self {
+            locked,
+            shared,
+            dirty: false,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for ArcmGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.locked
+    }
+}
+
+impl<T> DerefMut for ArcmGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.locked
+    }
+}
+
+impl<T> Drop for ArcmGuard<'_, T> {
+    fn drop(&mut self) {
+        // Runs before the fields drop, so the lock is still held
+        if self.dirty {
+            self.shared.changed(&self.locked);
+        }
+    }
+}
+
+impl<T: Debug> Debug for ArcmGuard<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArcmGuard").field(&**self).finish()
+    }
+}
+
+/// Restores an Arcm's original value when dropped, returned by
+/// [`Arcm::scoped_replace`]. It does not hold the lock in between.
+pub struct ScopeGuard<T> {
+    original: Option<T>,
+    arcm: Arcm<T>,
+}
+
+impl<T> ScopeGuard<T> {
+    /// Returns the value that will be restored
+    pub fn original(&self) -> &T {
+        self.original
+            .as_ref()
+            .expect("ScopeGuard holds its value until dropped")
     }
 }
 
-impl<T: Clone> Clone for Arcm<T> {
+impl<T> Drop for ScopeGuard<T> {
+    fn drop(&mut self) {
+        if let Some(original) = self.original.take() {
+            self.arcm.set(original);
+        }
+    }
+}
+
+impl<T: Debug> Debug for ScopeGuard<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScopeGuard")
+            .field("original", &self.original)
+            .finish()
+    }
+}
+
+/// A held lock on an Arcm's value that keeps its own handle, returned by
+/// [`Arcm::lock_owned`]. Otherwise behaves like [`ArcmGuard`].
+pub struct OwnedArcmGuard<T: 'static> {
+    // Borrows from the allocations `arcm` keeps alive, so it must drop first
+    locked: Locked<'static, T>,
+    arcm: Arcm<T>,
+    dirty: bool,
+    // Like MutexGuard, the lock must be released on the thread that took it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<T> OwnedArcmGuard<T> {
+    /// Returns the handle whose lock this guard holds
+    pub fn arcm(this: &Self) -> &Arcm<T> {
+        &this.arcm
+    }
+}
+
+impl<T> Deref for OwnedArcmGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.locked
+    }
+}
+
+impl<T> DerefMut for OwnedArcmGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.locked
+    }
+}
+
+impl<T> Drop for OwnedArcmGuard<T> {
+    fn drop(&mut self) {
+        // Runs before the fields drop, so the lock is still held
+        if self.dirty {
+            self.arcm.shared.changed(&self.locked);
+        }
+    }
+}
+
+impl<T: Debug> Debug for OwnedArcmGuard<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("OwnedArcmGuard").field(&**self).finish()
+    }
+}
+
+// Crate-internal access for the free functions that lock several handles at once
+impl<T> Arcm<T> {
+    /// Address of the value allocation, used to order locks across handles
+    pub(crate) fn addr(&self) -> usize {
+        Arc::as_ptr(&self.inner) as usize
+    }
+
+    pub(crate) fn guard(&self) -> Locked<'_, T> {
+        self.acquire()
+    }
+
+    pub(crate) fn changed(&self, value: &T) {
+        self.shared.changed(value);
+    }
+}
+
+impl<T> Clone for Arcm<T> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// Prints `Arcm(value)`. Never blocks: a locked Arcm, e.g. one formatted
+/// from inside its own `modify`, prints `Arcm(<locked>)`.
+impl<T: Debug> Debug for Arcm<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt_debug(f, "Arcm", &self.inner)
+    }
+}
+
+/// Prints the contained value, or `<locked>` instead of blocking while the
+/// lock is held, so logging from inside a critical section cannot deadlock
+impl<T: Display> Display for Arcm<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match try_lock(&self.inner) {
+            Some(value) => Display::fmt(&*value, f),
+            None => f.write_str("<locked>"),
         }
     }
 }
 
-impl<T: Clone + Debug> Debug for Arcm<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Arcm").field("inner", &self.inner).finish()
+/// Compares the contained values, locking both handles in a consistent order.
+/// Handles sharing an allocation are compared under a single lock.
+impl<T: PartialEq> PartialEq for Arcm<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value_eq(other)
+    }
+}
+
+impl<T: Eq> Eq for Arcm<T> {}
+
+/// Orders by the contained values, locking like [`PartialEq`].
+///
+/// Mutating an Arcm while it sits in an ordered container such as a
+/// `BinaryHeap` or `BTreeMap` breaks that container's invariants; the ordering
+/// is only as stable as the values.
+impl<T: PartialOrd> PartialOrd for Arcm<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            let value = self.acquire();
+            return value.partial_cmp(&value);
+        }
+        let (a, b) = Arcm::acquire_pair(self, other);
+        (*a).partial_cmp(&*b)
     }
 }
 
-impl<T: Clone + Default> Default for Arcm<T> {
+impl<T: Ord> Ord for Arcm<T> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return CmpOrdering::Equal;
+        }
+        let (a, b) = Arcm::acquire_pair(self, other);
+        (*a).cmp(&*b)
+    }
+}
+
+/// Hashes the contained value.
+///
+/// The hash follows the value, so mutating an Arcm while it is used as a
+/// key in a `HashMap` or `HashSet` leaves the collection inconsistent; keeping
+/// keys unchanged after insertion is the caller's responsibility.
+impl<T: Hash> Hash for Arcm<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.acquire().hash(state);
+    }
+}
+
+macro_rules! impl_op_assign {
+    ($($op:ident $method:ident),*) => {$(
+        /// Applies the operator to the value under the lock. Each use locks
+        /// separately, so several in a row are not one atomic transaction; use
+        /// [`Arcm::modify`] for that.
+        impl<T: $op<Rhs>, Rhs> $op<Rhs> for Arcm<T> {
+            fn $method(&mut self, rhs: Rhs) {
+                self.modify(|value| value.$method(rhs));
+            }
+        }
+    )*};
+}
+
+impl_op_assign!(
+    AddAssign add_assign,
+    SubAssign sub_assign,
+    MulAssign mul_assign,
+    DivAssign div_assign,
+    RemAssign rem_assign
+);
+
+impl<T: Default> Default for Arcm<T> {
     fn default() -> Self {
         Self::new(T::default())
     }
 }
 
-impl<T: Clone> From<T> for Arcm<T> {
+impl<T> From<T> for Arcm<T> {
     fn from(value: T) -> Self {
         Self::new(value)
     }
 }
 
+/// Wraps the `Ok` value, forwarding the error. Annotate the target type when
+/// calling this, since an `Arcm<Result<T, E>>` can also be built from a
+/// `Result` through [`From`].
+impl<T, E> TryFrom<Result<T, E>> for Arcm<T> {
+    type Error = E;
+
+    fn try_from(result: Result<T, E>) -> Result<Self, E> {
+        result.map(Self::new)
+    }
+}
+
 /// A weak reference wrapper for Arcm
-pub struct WeakArcm<T: Clone> {
+pub struct WeakArcm<T> {
     inner: Weak<Mutex<T>>,
+    shared: Weak<Shared<T>>,
 }
 
-impl<T: Clone> WeakArcm<T> {
+impl<T> WeakArcm<T> {
+    pub(crate) fn upgrade(&self) -> Option<Arcm<T>> {
+        Some(Arcm {
+            inner: self.inner.upgrade()?,
+            shared: self.shared.upgrade()?,
+        })
+    }
+
     /// Attempts to modify the value if the original Arcm still exists
     pub fn modify<F, R>(&self, f: F) -> Option<R>
     where
         F: FnOnce(&mut T) -> R,
     {
-        self.inner.upgrade().map(|arc| {
-            let mut guard = arc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
-            f(&mut *guard)
-        })
+        self.upgrade().map(|arcm| arcm.modify(f))
+    }
+
+    /// Attempts to replace the value if the original Arcm still exists
+    pub fn replace(&self, value: T) -> Option<T> {
+        self.upgrade().map(|arcm| arcm.replace(value))
+    }
+
+    /// Like [`Arcm::is_poisoned`]; false if the original Arcm is gone
+    pub fn is_poisoned(&self) -> bool {
+        self.inner
+            .upgrade()
+            .map(|inner| is_poisoned(&inner))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if both weak references point to the same Arcm allocation
+    pub fn ptr_eq(&self, other: &WeakArcm<T>) -> bool {
+        Weak::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns true once every strong Arcm has been dropped, without
+    /// upgrading
+    pub fn is_expired(&self) -> bool {
+        self.inner.strong_count() == 0
+    }
+
+    /// Upgrades once and runs `f` with the resulting strong handle, so several
+    /// operations see the Arcm stay alive throughout. Returns None without
+    /// calling `f` if the original Arcm is gone.
+    pub fn with_upgraded<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&Arcm<T>) -> R,
+    {
+        self.upgrade().map(|arcm| f(&arcm))
+    }
+
+    /// Blocks until every strong Arcm has been dropped, returning true, or
+    /// until `timeout` passes, returning false. `None` waits indefinitely.
+    /// Polls with backoff, so it may return up to a millisecond late.
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn wait_until_dropped(&self, timeout: Option<Duration>) -> bool {
+        poll_until(timeout, || self.is_expired())
     }
+}
 
+impl<T: Clone> WeakArcm<T> {
     /// Attempts to get a copy of the value if the original Arcm still exists
     pub fn value(&self) -> Option<T> {
-        self.inner.upgrade().map(|arc| match arc.lock() {
-            Ok(guard) => guard.clone(),
-            Err(poisoned) => poisoned.into_inner().clone(),
-        })
+        self.upgrade().map(|arcm| arcm.value())
     }
 
-    /// Attempts to replace the value if the original Arcm still exists
-    pub fn replace(&self, value: T) -> Option<T> {
-        self.inner.upgrade().map(|arc| {
-            let mut guard = arc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
-            std::mem::replace(&mut *guard, value)
-        })
+    /// Like [`Arcm::try_value`]: returns None if the original Arcm is gone,
+    /// `Some(None)` if its lock is contended, and `Some(Some(value))` otherwise
+    pub fn try_value(&self) -> Option<Option<T>> {
+        self.upgrade().map(|arcm| arcm.try_value())
+    }
+}
+
+impl<T> Clone for WeakArcm<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Weak::clone(&self.inner),
+            shared: Weak::clone(&self.shared),
+        }
     }
 }
 
-impl<T: Clone> Debug for WeakArcm<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<T> Debug for WeakArcm<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("WeakArcm")
             .field("inner", &self.inner)
             .finish()
     }
 }
 
+/// Removes every weak reference whose Arcm has been dropped, without
+/// upgrading the live ones
+pub fn prune_weak<T>(weaks: &mut Vec<WeakArcm<T>>) {
+    weaks.retain(|weak| !weak.is_expired());
+}
+
 // Example usage and tests
 #[cfg(test)]
 mod tests {
@@ -180,25 +1263,461 @@ mod tests {
         assert_eq!(length, Some(4));
         assert_eq!(strong.value(), vec![1, 2, 3, 4]);
 
-        // After dropping the strong reference
-        drop(strong);
-        let result = weak.modify(|v| v.push(5));
-        assert_eq!(result, None);
+        // After dropping the strong reference
+        drop(strong);
+        let result = weak.modify(|v| v.push(5));
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_weak_ptr_eq_and_expiry() {
+        let a = Arcm::new(1);
+        let b = Arcm::new(1);
+        let mut registry = vec![a.downgrade(), b.downgrade()];
+
+        assert!(registry[0].ptr_eq(&a.clone().downgrade()));
+        assert!(!registry[0].ptr_eq(&registry[1]));
+
+        drop(a);
+        assert!(registry[0].is_expired());
+        assert!(!registry[1].is_expired());
+
+        registry.retain(|w| !w.is_expired());
+        assert_eq!(registry.len(), 1);
+        assert!(registry[0].ptr_eq(&b.downgrade()));
+    }
+
+    #[test]
+    fn test_with_upgraded() {
+        let counter = Arcm::new(0);
+        let weak = counter.downgrade();
+        let seen = weak.with_upgraded(|counter| {
+            counter.modify(|n| *n += 1);
+            counter.modify(|n| *n += 1);
+            counter.value()
+        });
+        assert_eq!(seen, Some(2));
+
+        drop(counter);
+        assert_eq!(
+            weak.with_upgraded(|_| panic!("called after drop")),
+            None::<()>
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_wait_until_dropped() {
+        use std::time::Duration;
+
+        let resource = Arcm::new(0);
+        let weak = resource.downgrade();
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                resource.spawn_with(|resource| {
+                    thread::sleep(Duration::from_millis(5));
+                    resource.modify(|n| *n += 1);
+                })
+            })
+            .collect();
+
+        assert!(!weak.wait_until_dropped(Some(Duration::from_millis(1))));
+        drop(resource);
+        assert!(weak.wait_until_dropped(None));
+        assert!(weak.wait_until_dropped(Some(Duration::ZERO)));
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_op_assign() {
+        let mut counter = Arcm::new(10);
+        let observer = counter.clone();
+        counter += 5;
+        counter -= 3;
+        counter *= 4;
+        counter /= 6;
+        counter %= 5;
+        assert_eq!(observer.value(), 3);
+        assert_eq!(observer.generation(), 5);
+
+        let mut total = Arcm::new(String::from("a"));
+        total += "b";
+        assert_eq!(total.value(), "ab");
+    }
+
+    #[test]
+    fn test_value_changed_since() {
+        let close = |a: &f64, b: &f64| (a - b).abs() < 0.01;
+        let reading = Arcm::new(1.0);
+        let seen = reading.value();
+
+        reading.set(1.001);
+        assert!(!reading.value_changed_since(&seen, close));
+        reading.set(1.5);
+        assert!(reading.value_changed_since(&seen, close));
+    }
+
+    #[test]
+    fn test_increment_decrement() {
+        let counter = Arcm::new(0u64);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        counter.increment(2);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.value(), 800);
+        assert_eq!(counter.decrement(300), 500);
+        assert_eq!(counter.increment(1), 501);
+        assert_eq!(counter.generation(), 402);
+    }
+
+    #[test]
+    fn test_clone_value_into() {
+        let shared = Arcm::new(vec![1u8, 2, 3]);
+        let mut buffer = Vec::with_capacity(64);
+        let ptr = buffer.as_ptr();
+
+        shared.clone_value_into(&mut buffer);
+        assert_eq!(buffer, vec![1, 2, 3]);
+
+        shared.modify(|v| v.push(4));
+        shared.clone_value_into(&mut buffer);
+        assert_eq!(buffer, vec![1, 2, 3, 4]);
+        assert_eq!(buffer.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_try_value() {
+        let v = Arcm::new(7);
+        let weak = v.downgrade();
+        assert_eq!(v.try_value(), Some(7));
+        assert_eq!(weak.try_value(), Some(Some(7)));
+
+        v.with(|_| {
+            let other = v.clone();
+            let contended = thread::spawn(move || other.try_value()).join().unwrap();
+            assert_eq!(contended, None);
+            assert_eq!(weak.try_value(), Some(None));
+        });
+
+        drop(v);
+        assert_eq!(weak.try_value(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn test_tracing_records_contention() {
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Recorder {
+            contended: Mutex<Vec<bool>>,
+        }
+
+        impl Visit for &Recorder {
+            fn record_bool(&mut self, field: &Field, value: bool) {
+                if field.name() == "contended" {
+                    self.contended.lock().unwrap().push(value);
+                }
+            }
+
+            fn record_debug(&mut self, _: &Field, _: &dyn Debug) {}
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, values: &Record<'_>) {
+                values.record(&mut &*self);
+            }
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let v = Arcm::new(0);
+
+        let dispatch = recorder.clone();
+        tracing::subscriber::with_default(dispatch, || v.modify(|v| *v += 1));
+
+        let (dispatch, other) = (recorder.clone(), v.clone());
+        let contender = v.with(|_| {
+            let handle = thread::spawn(move || {
+                tracing::subscriber::with_default(dispatch, || other.replace(5))
+            });
+            thread::sleep(Duration::from_millis(50));
+            handle
+        });
+        assert_eq!(contender.join().unwrap(), 1);
+        assert_eq!(*recorder.contended.lock().unwrap(), vec![false, true]);
+    }
+
+    #[test]
+    fn test_scoped_replace() {
+        let config = Arcm::new("production");
+        {
+            let guard = config.scoped_replace("test");
+            assert_eq!(*guard.original(), "production");
+            assert_eq!(config.value(), "test");
+        }
+        assert_eq!(config.value(), "production");
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = config.scoped_replace("test");
+            panic!("test failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(config.value(), "production");
+    }
+
+    #[test]
+    fn test_swap_with() {
+        let active = Arcm::new(vec![1, 2]);
+        let flushed = Arcm::new(Vec::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let (active, flushed) = (active.clone(), flushed.clone());
+                thread::spawn(move || {
+                    active.swap_with(vec![i], |retired| {
+                        flushed.modify(|f| f.extend(retired));
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut all = flushed.value();
+        all.extend(active.value());
+        all.sort();
+        assert_eq!(all, vec![0, 1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_spawn_with() {
+        let counter = Arcm::new(0);
+        let workers: Vec<_> = (0..4)
+            .map(|_| counter.spawn_with(|c| c.modify(|v| *v += 1)))
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(counter.value(), 4);
+    }
+
+    #[test]
+    fn test_into_inner_or_clone() {
+        struct CountClones(u32);
+        impl Clone for CountClones {
+            fn clone(&self) -> Self {
+                CountClones(self.0 + 1)
+            }
+        }
+
+        let unique = Arcm::new(CountClones(0));
+        let weak = unique.downgrade();
+        assert_eq!(unique.into_inner_or_clone().0, 0);
+        assert!(weak.is_expired());
+
+        let shared = Arcm::new(CountClones(0));
+        let other = shared.clone();
+        assert_eq!(shared.into_inner_or_clone().0, 1);
+        assert_eq!(other.into_inner_or_clone().0, 0);
+    }
+
+    #[test]
+    fn test_map_in_place() {
+        let raw = Arcm::new(vec![b'4', b'2']);
+        let weak = raw.downgrade();
+        let parsed = raw.map_in_place(|bytes| String::from_utf8(bytes).unwrap());
+        assert_eq!(parsed.value(), "42");
+        assert!(weak.is_expired());
+
+        let shared = Arcm::new(String::from("7"));
+        let other = shared.clone();
+        let number = shared.map_in_place(|s| s.parse::<i32>().unwrap());
+        assert_eq!(number.value(), 7);
+        assert_eq!(other.value(), "7");
+    }
+
+    #[test]
+    fn test_overwrite_from() {
+        let live = Arcm::new(vec!["old"]);
+        let observer = live.clone();
+        let rebuilt = Arcm::new(vec!["new", "graph"]);
+
+        live.overwrite_from(&rebuilt);
+        assert_eq!(observer.value(), vec!["new", "graph"]);
+        assert_eq!(live.generation(), 1);
+
+        // Still independent allocations
+        rebuilt.modify(|v| v.clear());
+        assert_eq!(observer.value().len(), 2);
+
+        live.overwrite_from(&observer);
+        assert_eq!(live.generation(), 1);
+    }
+
+    #[test]
+    fn test_update() {
+        let state = Arcm::new(vec![1]);
+        let (before, after) = state.update(|v| v.push(2));
+        assert_eq!(before, vec![1]);
+        assert_eq!(after, vec![1, 2]);
+        assert_eq!(state.generation(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let doc = Arcm::new(String::from("draft"));
+        let checkpoint = doc.snapshot();
+        assert_eq!(checkpoint.value(), "draft");
+        assert_eq!(checkpoint.generation(), doc.generation());
+
+        doc.modify(|s| s.push_str(" v2"));
+        assert!(!doc.restore(checkpoint));
+        assert_eq!(doc.value(), "draft");
+
+        let checkpoint = doc.snapshot();
+        assert!(doc.restore(checkpoint.clone()));
+        assert_eq!(checkpoint.into_inner(), "draft");
+    }
+
+    #[test]
+    fn test_weak_clone() {
+        let strong = Arcm::new(1);
+        let weak = strong.weak_clone();
+        let copy = weak.clone();
+        assert!(copy.ptr_eq(&weak));
+        assert_eq!(Arc::strong_count(&strong.inner), 1);
+
+        copy.modify(|v| *v = 2);
+        assert_eq!(weak.value(), Some(2));
+        drop(strong);
+        assert!(copy.is_expired());
+    }
+
+    #[test]
+    fn test_prune_weak() {
+        let live = Arcm::new(1);
+        let dead = Arcm::new(2);
+        let mut weaks = vec![dead.downgrade(), live.downgrade(), dead.downgrade()];
+
+        drop(dead);
+        prune_weak(&mut weaks);
+        assert_eq!(weaks.len(), 1);
+        assert!(weaks[0].ptr_eq(&live.downgrade()));
+    }
+
+    #[test]
+    fn test_default() {
+        // Creates an Arcm containing an empty Vec
+        let vec_arcm: Arcm<Vec<i32>> = Arcm::default();
+        assert_eq!(vec_arcm.value(), Vec::new());
+
+        // Creates an Arcm containing 0
+        let int_arcm: Arcm<i32> = Arcm::default();
+        assert_eq!(int_arcm.value(), 0);
+
+        // Creates an Arcm containing empty String
+        let string_arcm: Arcm<String> = Arcm::default();
+        assert_eq!(string_arcm.value(), String::new());
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_eq_and_hash() {
+        use std::collections::HashMap;
+
+        let a = Arcm::new("key".to_string());
+        let b = Arcm::new("key".to_string());
+        assert_eq!(a, b);
+        assert_eq!(a, a.clone());
+        assert_ne!(a, Arcm::new("other".to_string()));
+
+        // Same-allocation comparison locks only once
+        let nan = Arcm::new(f64::NAN);
+        assert_ne!(nan, nan.clone());
+
+        let mut map = HashMap::new();
+        map.insert(a, 1);
+        assert_eq!(map.get(&b), Some(&1));
+    }
+
+    #[test]
+    fn test_try_new() {
+        fn parse_port(raw: &str) -> Result<u16, core::num::ParseIntError> {
+            raw.parse()
+        }
+
+        let port = Arcm::try_new(|| parse_port("8080")).unwrap();
+        assert_eq!(port.value(), 8080);
+        assert!(Arcm::try_new(|| parse_port("http")).is_err());
+
+        let port: Arcm<u16> = Arcm::try_from(parse_port("443")).unwrap();
+        assert_eq!(port.value(), 443);
+        let err: Result<Arcm<u16>, _> = parse_port("").try_into();
+        assert!(err.is_err());
     }
 
     #[test]
-    fn test_default() {
-        // Creates an Arcm containing an empty Vec
-        let vec_arcm: Arcm<Vec<i32>> = Arcm::default();
-        assert_eq!(vec_arcm.value(), Vec::new());
+    fn test_ord() {
+        use std::collections::BinaryHeap;
 
-        // Creates an Arcm containing 0
-        let int_arcm: Arcm<i32> = Arcm::default();
-        assert_eq!(int_arcm.value(), 0);
+        let mut heap = BinaryHeap::new();
+        for priority in [3, 7, 1] {
+            heap.push(Arcm::new(priority));
+        }
+        let low = Arcm::new(1);
+        assert!(low < Arcm::new(2));
+        assert_eq!(low.cmp(&low.clone()), CmpOrdering::Equal);
+        assert_eq!(heap.pop().unwrap().value(), 7);
+        assert_eq!(heap.pop().unwrap().value(), 3);
 
-        // Creates an Arcm containing empty String
-        let string_arcm: Arcm<String> = Arcm::default();
-        assert_eq!(string_arcm.value(), String::new());
+        let nan = Arcm::new(f64::NAN);
+        assert_eq!(nan.partial_cmp(&nan.clone()), None);
+    }
+
+    #[test]
+    fn test_debug_shows_value() {
+        let v = Arcm::new(42);
+        assert_eq!(format!("{:?}", v), "Arcm(42)");
+        assert_eq!(
+            format!("{:#?}", Arcm::new(vec![1])),
+            "Arcm(\n    [\n        1,\n    ],\n)"
+        );
+
+        let inside = v.modify(|_| format!("{:?}", v));
+        assert_eq!(inside, "Arcm(<locked>)");
+
+        let other = v.clone();
+        let _ = thread::spawn(move || other.modify(|_| panic!("poison"))).join();
+        if cfg!(not(any(feature = "spin", feature = "parking_lot"))) {
+            assert_eq!(format!("{:?}", v), "Arcm(42, <poisoned>)");
+        }
     }
 
     #[test]
@@ -216,6 +1735,16 @@ mod tests {
         assert!(arcm3.value().is_empty());
     }
 
+    #[test]
+    fn test_display() {
+        let arcm = Arcm::new(42);
+        assert_eq!(arcm.to_string(), "42");
+        assert_eq!(arcm.modify(|_| arcm.to_string()), "<locked>");
+
+        let arcm = Arcm::new("hello".to_string());
+        assert_eq!(format!("[{:>7}]", arcm), "[  hello]");
+    }
+
     #[test]
     fn test_arcm_poisoned_mutex_recovery() {
         let arcm = Arcm::new(42);
@@ -245,6 +1774,27 @@ mod tests {
         assert_eq!(arcm.value(), 100);
     }
 
+    #[test]
+    fn test_arcm_is_poisoned() {
+        let arcm = Arcm::new(42);
+        let weak = arcm.downgrade();
+        assert!(!arcm.is_poisoned());
+        assert!(!weak.is_poisoned());
+
+        let arcm_clone = arcm.clone();
+        let _ = thread::spawn(move || arcm_clone.modify(|_| panic!("poison"))).join();
+
+        // Recovery is silent, but still reported
+        let poisons = cfg!(not(any(feature = "spin", feature = "parking_lot")));
+        assert_eq!(arcm.value(), 42);
+        assert_eq!(arcm.is_poisoned(), poisons);
+        assert_eq!(weak.is_poisoned(), poisons);
+
+        // A weak handle whose Arcm is gone reports false
+        drop(arcm);
+        assert!(!weak.is_poisoned());
+    }
+
     #[test]
     fn test_arcm_replace() {
         let arcm = Arcm::new(42);
@@ -285,6 +1835,584 @@ mod tests {
         assert_eq!(arcm.value(), 100);
     }
 
+    #[test]
+    fn test_non_clone_type() {
+        #[derive(Debug, PartialEq)]
+        struct Handle(u32);
+
+        let arcm = Arcm::new(Handle(1));
+        let arcm2 = arcm.clone();
+
+        arcm.modify(|h| h.0 += 1);
+        assert_eq!(arcm2.with(|h| h.0), 2);
+
+        let old = arcm.replace(Handle(10));
+        assert_eq!(old, Handle(2));
+        arcm.set(Handle(20));
+        assert!(arcm2.compare_and_set(&Handle(20), Handle(30)));
+        assert_eq!(arcm.downgrade().modify(|h| h.0), Some(30));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_non_clone_resource_across_threads() {
+        struct Connection {
+            sent: Vec<u32>,
+        }
+
+        let conn = Arcm::new(Connection { sent: Vec::new() });
+        let handles: Vec<_> = (0..4)
+            .map(|i| conn.spawn_with(move |conn| conn.modify(|c| c.sent.push(i))))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let old = conn.replace(Connection { sent: Vec::new() });
+        assert_eq!(old.sent.len(), 4);
+        assert!(conn.with(|c| c.sent.is_empty()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_lock_or_timeout() {
+        use std::time::{Duration, Instant};
+
+        let v = Arcm::new(1);
+        *v.lock_or_timeout(Duration::from_millis(10)).unwrap() += 1;
+        assert_eq!(v.value(), 2);
+        assert_eq!(v.generation(), 1);
+
+        let other = v.clone();
+        let held = v.lock();
+        let result = thread::spawn(move || {
+            let start = Instant::now();
+            let result = other.lock_or_timeout(Duration::from_millis(20)).map(|_| ());
+            assert!(start.elapsed() >= Duration::from_millis(20));
+            result
+        })
+        .join()
+        .unwrap();
+        assert_eq!(result, Err(LockError::Timeout));
+        drop(held);
+
+        let other = v.clone();
+        let _ = thread::spawn(move || other.modify(|_| panic!("poison"))).join();
+        let poisons = cfg!(not(any(feature = "spin", feature = "parking_lot")));
+        let checked = v.lock_or_timeout_checked(Duration::from_millis(10));
+        assert_eq!(checked.is_err(), poisons);
+        drop(checked);
+        assert_eq!(*v.lock_or_timeout(Duration::from_millis(10)).unwrap(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_with_timeout() {
+        use std::time::Duration;
+
+        let v = Arcm::new(1);
+        let doubled = v.with_timeout(Duration::from_millis(10), |n| {
+            *n *= 2;
+            *n
+        });
+        assert_eq!(doubled, Ok(2));
+        assert_eq!(v.generation(), 1);
+
+        let other = v.clone();
+        let held = v.lock();
+        let result =
+            thread::spawn(move || other.with_timeout(Duration::from_millis(20), |n| *n += 1))
+                .join()
+                .unwrap();
+        assert_eq!(result, Err(TimedOut));
+        drop(held);
+        assert_eq!(v.value(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "fair")]
+    fn test_fair_handoff() {
+        use std::time::Duration;
+
+        let order = Arcm::new(Vec::new());
+        let held = order.lock();
+        let waiter = order.spawn_with(|order| order.modify(|o| o.push("waiter")));
+        // Give the waiter time to queue up behind the held lock
+        thread::sleep(Duration::from_millis(50));
+
+        // The queued waiter is handed the lock before this thread can retake it
+        drop(held);
+        order.modify(|o| o.push("releaser"));
+        waiter.join().unwrap();
+        assert_eq!(order.value(), vec!["waiter", "releaser"]);
+    }
+
+    #[test]
+    fn test_lock_owned() {
+        struct Session {
+            guard: OwnedArcmGuard<Vec<u32>>,
+        }
+
+        let log = Arcm::new(Vec::new());
+        let mut session = {
+            let handle = log.clone();
+            Session {
+                guard: handle.lock_owned(),
+            }
+        };
+        session.guard.push(1);
+        session.guard.push(2);
+        assert!(OwnedArcmGuard::arcm(&session.guard).addr() == log.addr());
+        assert!(log.try_value().is_none());
+
+        drop(session);
+        assert_eq!(log.value(), vec![1, 2]);
+        assert_eq!(log.generation(), 1);
+        assert_eq!(Arc::strong_count(&log.inner), 1);
+    }
+
+    #[test]
+    fn test_clone_and_lock() {
+        let inbox = Arcm::new(vec![1]);
+        let (handle, mut guard) = inbox.clone_and_lock();
+        guard.push(2);
+        assert!(handle.addr() == inbox.addr());
+        assert!(handle.try_value().is_none());
+
+        drop(guard);
+        assert_eq!(handle.value(), vec![1, 2]);
+        assert_eq!(Arc::strong_count(&inbox.inner), 2);
+    }
+
+    #[test]
+    fn test_lock_guard() {
+        #[derive(Clone, Debug, Default, PartialEq)]
+        struct Config {
+            a: i32,
+            b: i32,
+        }
+
+        let arcm = Arcm::new(Config::default());
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&notified);
+        arcm.subscribe(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        {
+            let mut guard = arcm.lock();
+            guard.a = 1;
+            guard.b = 2;
+            assert_eq!(format!("{:?}", guard), "ArcmGuard(Config { a: 1, b: 2 })");
+        }
+        assert_eq!(arcm.value(), Config { a: 1, b: 2 });
+        // One notification and one generation bump for the whole burst
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+        assert_eq!(arcm.generation(), 1);
+
+        // Read-only use of the guard isn't a modification
+        assert_eq!(arcm.lock().a, 1);
+        assert_eq!(arcm.generation(), 1);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let arcm = Arcm::new(1);
+        let mut seen = None;
+
+        // Chaining into modify must not deadlock
+        arcm.inspect(|v| seen = Some(*v)).modify(|v| *v += 1);
+        assert_eq!(seen, Some(1));
+        assert_eq!(arcm.value(), 2);
+    }
+
+    #[test]
+    fn test_into_arcmo() {
+        let arcm = Arcm::new(42);
+        let other = arcm.clone();
+
+        let arcmo = arcm.into_arcmo();
+        assert_eq!(arcmo.value(), Some(42));
+
+        // The conversion doesn't share storage with the original
+        arcmo.replace(100);
+        assert_eq!(other.value(), 42);
+    }
+
+    #[test]
+    fn test_set() {
+        let arcm = Arcm::new(42);
+        let arcm2 = arcm.clone();
+
+        arcm.set(100);
+        assert_eq!(arcm2.value(), 100);
+        assert_eq!(arcm.generation(), 1);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut arcm = Arcm::new(vec![1]);
+        arcm.get_mut().unwrap().push(2);
+        assert_eq!(arcm.value(), vec![1, 2]);
+
+        // Not available while shared, strongly or weakly
+        let other = arcm.clone();
+        assert!(arcm.get_mut().is_none());
+        drop(other);
+
+        let weak = arcm.downgrade();
+        assert!(arcm.get_mut().is_none());
+        drop(weak);
+
+        assert!(arcm.get_mut().is_some());
+    }
+
+    #[test]
+    fn test_make_mut() {
+        // Uniquely owned: mutates in place
+        let mut arcm = Arcm::new(vec![1]);
+        arcm.make_mut().push(2);
+        assert_eq!(arcm.value(), vec![1, 2]);
+
+        // Shared: forks on write and leaves the other handles alone
+        let reader = arcm.clone();
+        arcm.make_mut().push(3);
+        assert_eq!(arcm.value(), vec![1, 2, 3]);
+        assert_eq!(reader.value(), vec![1, 2]);
+
+        // The fork is now independent
+        reader.modify(|v| v.clear());
+        assert_eq!(arcm.value(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_new_cyclic() {
+        struct Node {
+            name: String,
+            me: WeakArcm<Node>,
+        }
+
+        let node = Arcm::new_cyclic(|me| {
+            // Not upgradable while still under construction
+            assert!(me.upgrade().is_none());
+            Node {
+                name: "root".to_string(),
+                me: WeakArcm {
+                    inner: me.inner.clone(),
+                    shared: me.shared.clone(),
+                },
+            }
+        });
+
+        let points_home = node.with(|n| {
+            let me = n.me.upgrade().unwrap();
+            Arc::ptr_eq(&me.inner, &node.inner)
+        });
+        assert!(points_home);
+        assert_eq!(node.with(|n| n.name.clone()), "root");
+
+        // The self reference is weak, so dropping the last handle frees the node
+        let weak = node.downgrade();
+        drop(node);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_arc_interop() {
+        let arcm = Arcm::new(1);
+        let raw = arcm.as_arc();
+        *lock(&raw) = 2;
+        assert_eq!(arcm.value(), 2);
+
+        let raw = Arc::new(crate::Mutex::new(10));
+        let adopted = Arcm::from_arc(Arc::clone(&raw));
+        adopted.modify(|v| *v += 1);
+        assert_eq!(*lock(&raw), 11);
+        assert!(Arc::ptr_eq(&adopted.as_arc(), &raw));
+    }
+
+    #[test]
+    fn test_fetch_update() {
+        let arcm = Arcm::new(vec![1, 2, 3]);
+
+        let result: Result<usize, &str> = arcm.fetch_update(|v| {
+            v.push(4);
+            Ok(v.len())
+        });
+        assert_eq!(result, Ok(4));
+        assert_eq!(arcm.value(), vec![1, 2, 3, 4]);
+
+        // A failure partway through leaves the value untouched
+        let result: Result<usize, &str> = arcm.fetch_update(|v| {
+            v.push(5);
+            v.clear();
+            Err("validation failed")
+        });
+        assert_eq!(result, Err("validation failed"));
+        assert_eq!(arcm.value(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_compare_and_set() {
+        #[derive(Clone, Debug, PartialEq)]
+        enum State {
+            Idle,
+            Running,
+            Done,
+        }
+
+        let state = Arcm::new(State::Idle);
+        assert!(state.compare_and_set(&State::Idle, State::Running));
+        assert_eq!(state.value(), State::Running);
+
+        // Stale expectation leaves the value alone
+        assert!(!state.compare_and_set(&State::Idle, State::Done));
+        assert_eq!(state.value(), State::Running);
+    }
+
+    #[test]
+    fn test_replace_if_generation() {
+        let v = Arcm::new(String::from("a"));
+        let seen = v.generation();
+        assert_eq!(v.replace_if_generation(seen, String::from("b")), Ok(1));
+        assert_eq!(v.replace_if_generation(seen, String::from("c")), Err(1));
+        assert_eq!(v.value(), "b");
+
+        let counter = Arcm::new(0);
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || loop {
+                    let snapshot = counter.snapshot();
+                    let next = *snapshot.value() + 1;
+                    if counter
+                        .replace_if_generation(snapshot.generation(), next)
+                        .is_ok()
+                    {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.value(), 8);
+    }
+
+    #[test]
+    fn test_with_snapshot() {
+        let listeners = Arcm::new(vec![1, 2, 3]);
+        let total = listeners.with_snapshot(|snapshot| {
+            // Not holding the lock, so the same Arcm can be used in here
+            listeners.modify(|l| l.clear());
+            snapshot.iter().sum::<i32>()
+        });
+        assert_eq!(total, 6);
+        assert!(listeners.value().is_empty());
+    }
+
+    #[test]
+    fn test_value_eq() {
+        let a = Arcm::new(String::from("x"));
+        let b = Arcm::new(String::from("x"));
+        assert!(a.value_eq(&b));
+        assert!(a.value_eq(&a.clone()));
+        assert!(a.value_eq_to(&String::from("x")));
+
+        b.set(String::from("y"));
+        assert!(!a.value_eq(&b));
+        assert!(!b.value_eq_to(&String::from("x")));
+    }
+
+    #[test]
+    fn test_set_if_changed() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let v = Arcm::new(1);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        v.subscribe(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(!v.set_if_changed(1));
+        assert_eq!(v.generation(), 0);
+        assert!(v.set_if_changed(2));
+        assert_eq!(v.value(), 2);
+        assert_eq!(v.generation(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_compare_and_set_threads() {
+        let arcm = Arcm::new(0);
+        let winners: usize = (0..8)
+            .map(|_| {
+                let arcm = arcm.clone();
+                thread::spawn(move || arcm.compare_and_set(&0, 1) as usize)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+
+        assert_eq!(winners, 1);
+        assert_eq!(arcm.value(), 1);
+    }
+
+    #[test]
+    fn test_generation() {
+        let arcm = Arcm::new(0);
+        assert_eq!(arcm.generation(), 0);
+
+        arcm.modify(|v| *v += 1);
+        arcm.replace(5);
+        assert_eq!(arcm.generation(), 2);
+
+        // Reads and failed updates leave the generation alone
+        let saved = arcm.generation();
+        arcm.value();
+        assert!(!arcm.compare_and_set(&0, 1));
+        assert_eq!(arcm.generation(), saved);
+
+        // Clones share the counter
+        arcm.clone().modify(|v| *v += 1);
+        assert_ne!(arcm.generation(), saved);
+    }
+
+    #[cfg(not(feature = "spin"))]
+    #[test]
+    fn test_wait_until() {
+        use std::collections::VecDeque;
+        use std::time::Duration;
+
+        let queue = Arcm::new(VecDeque::new());
+
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                (0..3)
+                    .map(|_| queue.wait_until(|q| !q.is_empty(), |q| q.pop_front().unwrap()))
+                    .collect::<Vec<i32>>()
+            })
+        };
+
+        for i in 1..=3 {
+            thread::sleep(Duration::from_millis(10));
+            queue.modify(|q| q.push_back(i));
+        }
+
+        assert_eq!(consumer.join().unwrap(), vec![1, 2, 3]);
+        assert!(queue.value().is_empty());
+    }
+
+    #[cfg(not(feature = "spin"))]
+    #[test]
+    fn test_notify_all() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        let arcm = Arcm::new(0);
+        let ready = Arc::new(AtomicBool::new(false));
+
+        let waiter = {
+            let arcm = arcm.clone();
+            let ready = Arc::clone(&ready);
+            thread::spawn(move || arcm.wait_until(|_| ready.load(Ordering::SeqCst), |v| *v))
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        // Flipped under the lock so the waiter can't miss the wakeup
+        arcm.with(|_| ready.store(true, Ordering::SeqCst));
+        arcm.notify_all();
+        assert_eq!(waiter.join().unwrap(), 0);
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    #[should_panic(expected = "reentrant lock on Arcm detected")]
+    fn test_reentrant_lock_detected() {
+        let arcm = Arcm::new(1);
+        arcm.modify(|_| arcm.value());
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    #[should_panic(expected = "reentrant lock on Arcm detected")]
+    fn test_reentrant_subscriber_detected() {
+        let arcm = Arcm::new(1);
+        let handle = arcm.downgrade();
+        arcm.subscribe(move |_| {
+            handle.value();
+        });
+        arcm.set(2);
+    }
+
+    #[test]
+    fn test_sequential_locking_across_threads() {
+        // Owner tracking must not misfire on ordinary contended use
+        let arcm = Arcm::new(0);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let arcm = arcm.clone();
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        arcm.modify(|v| *v += 1);
+                        arcm.value();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(arcm.value(), 2000);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        use std::sync::Mutex as StdMutex;
+
+        let arcm = Arcm::new(0);
+        let seen = Arc::new(StdMutex::new(Vec::new()));
+
+        let recorder = Arc::clone(&seen);
+        let id = arcm.subscribe(move |v| recorder.lock().unwrap().push(*v));
+
+        arcm.modify(|v| *v = 1);
+        arcm.replace(2);
+        // Clones and weak handles share the same subscriptions
+        arcm.clone().modify(|v| *v += 1);
+        arcm.downgrade().replace(4);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3, 4]);
+
+        // Failed conditional updates don't notify
+        assert!(!arcm.compare_and_set(&0, 5));
+        let _: Result<(), ()> = arcm.fetch_update(|_| Err(()));
+        assert_eq!(seen.lock().unwrap().len(), 4);
+
+        arcm.unsubscribe(id);
+        arcm.modify(|v| *v = 5);
+        assert_eq!(seen.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_subscribe_from_callback() {
+        let arcm = Arcm::new(0);
+        let handle = arcm.clone();
+
+        // Registering subscribers while being notified doesn't deadlock
+        arcm.subscribe(move |_| {
+            handle.subscribe(|_| {});
+        });
+        arcm.modify(|v| *v += 1);
+        arcm.modify(|v| *v += 1);
+        assert_eq!(lock(&arcm.shared.subscribers).list.len(), 3);
+    }
+
     #[test]
     fn test_weak_arcm_poisoned_mutex() {
         let strong = Arcm::new(42);
@@ -311,6 +2439,32 @@ mod tests {
         assert_eq!(strong.value(), 100);
     }
 
+    #[test]
+    fn test_checked_methods() {
+        let arcm = Arcm::new(42);
+        assert_eq!(arcm.value_checked(), Ok(42));
+        assert_eq!(arcm.try_modify_checked(|v| *v += 1), Ok(()));
+        assert_eq!(arcm.replace_checked(100), Ok(43));
+
+        let arcm_clone = arcm.clone();
+        let _ = thread::spawn(move || {
+            arcm_clone.modify(|_| panic!("Deliberate panic to poison mutex"));
+        })
+        .join();
+
+        // Only the std backend poisons
+        if cfg!(any(feature = "spin", feature = "parking_lot")) {
+            assert_eq!(arcm.value_checked(), Ok(100));
+        } else {
+            assert_eq!(arcm.value_checked(), Err(PoisonError));
+            assert_eq!(arcm.try_modify_checked(|v| *v = 0), Err(PoisonError));
+            assert_eq!(arcm.replace_checked(0), Err(PoisonError));
+
+            // The recovering methods still work and the value is untouched
+            assert_eq!(arcm.value(), 100);
+        }
+    }
+
     #[test]
     fn test_weak_arcm_replace() {
         let strong = Arcm::new(42);
@@ -370,4 +2524,15 @@ mod tests {
 
         assert_eq!(arcm.value(), threads * increments_per_thread);
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_force_poison() {
+        let poisons = cfg!(not(any(feature = "spin", feature = "parking_lot")));
+        let fresh = Arcm::new(0);
+        fresh.force_poison();
+        assert_eq!(fresh.is_poisoned(), poisons);
+        assert_eq!(fresh.value_checked().is_err(), poisons);
+        assert_eq!(fresh.value(), 0);
+    }
 }
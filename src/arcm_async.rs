@@ -0,0 +1,99 @@
+use alloc::sync::Arc;
+use core::fmt::Debug;
+use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// A shared value for async code, locked with `tokio::sync::Mutex` so guards
+/// can be held across `.await` points. Requires the `tokio` feature.
+///
+/// Waiting for the lock yields to the runtime instead of blocking the thread.
+/// Tokio's mutex never poisons.
+pub struct ArcmAsync<T> {
+    inner: Arc<Mutex<T>>,
+}
+
+impl<T> ArcmAsync<T> {
+    /// Creates a new ArcmAsync containing the given value
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(value)),
+        }
+    }
+
+    /// Modifies the contained value using the provided closure
+    pub async fn modify<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut *self.inner.lock().await)
+    }
+
+    /// Waits for the lock and returns a guard that owns a reference to the
+    /// value, so it can be stored or moved into a spawned task
+    pub async fn lock_owned(&self) -> OwnedMutexGuard<T> {
+        Arc::clone(&self.inner).lock_owned().await
+    }
+
+    /// Like [`ArcmAsync::lock_owned`], but returns None if the lock can't be
+    /// taken within `timeout`. Must be called within a Tokio runtime with the
+    /// time driver enabled.
+    pub async fn lock_owned_timeout(&self, timeout: Duration) -> Option<OwnedMutexGuard<T>> {
+        tokio::time::timeout(timeout, self.lock_owned()).await.ok()
+    }
+}
+
+impl<T: Clone> ArcmAsync<T> {
+    /// Returns a copy of the contained value
+    pub async fn value(&self) -> T {
+        self.inner.lock().await.clone()
+    }
+}
+
+impl<T> Clone for ArcmAsync<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Debug> Debug for ArcmAsync<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut tuple = f.debug_tuple("ArcmAsync");
+        match self.inner.try_lock() {
+            Ok(value) => tuple.field(&&*value),
+            Err(_) => tuple.field(&format_args!("<locked>")),
+        };
+        tuple.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lock_owned_timeout() {
+        let state = ArcmAsync::new(1);
+        state.modify(|n| *n += 1).await;
+
+        let mut guard = state
+            .lock_owned_timeout(Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(format!("{:?}", state), "ArcmAsync(<locked>)");
+
+        // Contended while the guard is held, even across an await
+        tokio::task::yield_now().await;
+        let other = state.clone();
+        assert!(other
+            .lock_owned_timeout(Duration::from_millis(10))
+            .await
+            .is_none());
+
+        *guard += 1;
+        drop(guard);
+        assert_eq!(state.value().await, 3);
+        assert_eq!(format!("{:?}", state), "ArcmAsync(3)");
+    }
+}
@@ -0,0 +1,300 @@
+use alloc::sync::{Arc, Weak};
+use core::fmt::Debug;
+use core::sync::atomic::Ordering;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Types with a matching atomic in `core::sync::atomic`, usable in an
+/// [`ArcmAtomic`]. Implemented for `bool` and the primitive integers, each
+/// only on targets with atomics of that width; it can't be implemented outside
+/// this crate.
+pub trait AtomicValue: Copy + private::Sealed {
+    #[doc(hidden)]
+    type Atomic: Send + Sync;
+
+    #[doc(hidden)]
+    fn new_atomic(value: Self) -> Self::Atomic;
+    #[doc(hidden)]
+    fn load(atomic: &Self::Atomic, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn store(atomic: &Self::Atomic, value: Self, order: Ordering);
+    #[doc(hidden)]
+    fn swap(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn compare_exchange(
+        atomic: &Self::Atomic,
+        current: Self,
+        new: Self,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Self, Self>;
+}
+
+/// The integer [`AtomicValue`]s, which also support arithmetic
+pub trait AtomicInteger: AtomicValue {
+    #[doc(hidden)]
+    fn fetch_add(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn fetch_sub(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+}
+
+macro_rules! impl_atomic_value {
+    ($($width:literal: $ty:ty => $atomic:ident),+ $(,)?) => {$(
+        #[cfg(target_has_atomic = $width)]
+        impl private::Sealed for $ty {}
+
+        #[cfg(target_has_atomic = $width)]
+        impl AtomicValue for $ty {
+            type Atomic = core::sync::atomic::$atomic;
+
+            fn new_atomic(value: Self) -> Self::Atomic {
+                Self::Atomic::new(value)
+            }
+
+            fn load(atomic: &Self::Atomic, order: Ordering) -> Self {
+                atomic.load(order)
+            }
+
+            fn store(atomic: &Self::Atomic, value: Self, order: Ordering) {
+                atomic.store(value, order)
+            }
+
+            fn swap(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self {
+                atomic.swap(value, order)
+            }
+
+            fn compare_exchange(
+                atomic: &Self::Atomic,
+                current: Self,
+                new: Self,
+                success: Ordering,
+                failure: Ordering,
+            ) -> Result<Self, Self> {
+                atomic.compare_exchange(current, new, success, failure)
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_atomic_integer {
+    ($($width:literal: $ty:ty),+ $(,)?) => {$(
+        #[cfg(target_has_atomic = $width)]
+        impl AtomicInteger for $ty {
+            fn fetch_add(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self {
+                atomic.fetch_add(value, order)
+            }
+
+            fn fetch_sub(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self {
+                atomic.fetch_sub(value, order)
+            }
+        }
+    )+};
+}
+
+impl_atomic_value! {
+    "8": bool => AtomicBool,
+    "8": u8 => AtomicU8,
+    "16": u16 => AtomicU16,
+    "32": u32 => AtomicU32,
+    "64": u64 => AtomicU64,
+    "ptr": usize => AtomicUsize,
+    "8": i8 => AtomicI8,
+    "16": i16 => AtomicI16,
+    "32": i32 => AtomicI32,
+    "64": i64 => AtomicI64,
+    "ptr": isize => AtomicIsize,
+}
+
+impl_atomic_integer! {
+    "8": u8,
+    "16": u16,
+    "32": u32,
+    "64": u64,
+    "ptr": usize,
+    "8": i8,
+    "16": i16,
+    "32": i32,
+    "64": i64,
+    "ptr": isize,
+}
+
+/// A lock-free counterpart to Arcm for flags and counters: an Arc around the
+/// matching atomic type, e.g. `ArcmAtomic<u64>` wraps an `AtomicU64`.
+///
+/// Clones share the value. Every operation takes an explicit [`Ordering`],
+/// as with the underlying atomic.
+pub struct ArcmAtomic<T: AtomicValue> {
+    inner: Arc<T::Atomic>,
+}
+
+impl<T: AtomicValue> ArcmAtomic<T> {
+    /// Creates a new ArcmAtomic holding the given value
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(T::new_atomic(value)),
+        }
+    }
+
+    /// Returns the current value
+    pub fn load(&self, order: Ordering) -> T {
+        T::load(&self.inner, order)
+    }
+
+    /// Sets the value
+    pub fn store(&self, value: T, order: Ordering) {
+        T::store(&self.inner, value, order)
+    }
+
+    /// Sets the value and returns the previous one
+    pub fn swap(&self, value: T, order: Ordering) -> T {
+        T::swap(&self.inner, value, order)
+    }
+
+    /// Sets the value to `new` if it is currently `current`. Returns the
+    /// previous value, as `Ok` if it was replaced and `Err` otherwise.
+    pub fn compare_exchange(
+        &self,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        T::compare_exchange(&self.inner, current, new, success, failure)
+    }
+
+    /// Returns a weak reference to the value
+    pub fn downgrade(&self) -> WeakArcmAtomic<T> {
+        WeakArcmAtomic {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+}
+
+impl<T: AtomicInteger> ArcmAtomic<T> {
+    /// Adds to the value, wrapping on overflow, and returns the previous value
+    pub fn fetch_add(&self, value: T, order: Ordering) -> T {
+        T::fetch_add(&self.inner, value, order)
+    }
+
+    /// Subtracts from the value, wrapping on overflow, and returns the
+    /// previous value
+    pub fn fetch_sub(&self, value: T, order: Ordering) -> T {
+        T::fetch_sub(&self.inner, value, order)
+    }
+}
+
+impl<T: AtomicValue> Clone for ArcmAtomic<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: AtomicValue + Default> Default for ArcmAtomic<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: AtomicValue + Debug> Debug for ArcmAtomic<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArcmAtomic")
+            .field(&self.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// A weak reference wrapper for ArcmAtomic
+pub struct WeakArcmAtomic<T: AtomicValue> {
+    inner: Weak<T::Atomic>,
+}
+
+impl<T: AtomicValue> WeakArcmAtomic<T> {
+    /// Returns the current value if the original ArcmAtomic still exists
+    pub fn load(&self, order: Ordering) -> Option<T> {
+        self.inner.upgrade().map(|atomic| T::load(&atomic, order))
+    }
+
+    /// Returns an ArcmAtomic sharing the value if the original still exists
+    pub fn upgrade(&self) -> Option<ArcmAtomic<T>> {
+        self.inner.upgrade().map(|inner| ArcmAtomic { inner })
+    }
+
+    /// Returns true once every strong ArcmAtomic has been dropped
+    pub fn is_expired(&self) -> bool {
+        self.inner.strong_count() == 0
+    }
+}
+
+impl<T: AtomicValue> Clone for WeakArcmAtomic<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Weak::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: AtomicValue> Debug for WeakArcmAtomic<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WeakArcmAtomic").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_counter_across_threads() {
+        let hits = ArcmAtomic::new(0u64);
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let hits = hits.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        hits.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 8000);
+        assert_eq!(hits.fetch_sub(1, Ordering::SeqCst), 8000);
+        assert_eq!(format!("{:?}", hits), "ArcmAtomic(7999)");
+    }
+
+    #[test]
+    fn test_flag() {
+        let ready = ArcmAtomic::<bool>::default();
+        assert_eq!(
+            ready.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire),
+            Ok(false)
+        );
+        assert_eq!(
+            ready.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire),
+            Err(true)
+        );
+        assert!(ready.swap(false, Ordering::AcqRel));
+        ready.store(true, Ordering::Release);
+        assert!(ready.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_weak() {
+        let level = ArcmAtomic::new(-1i32);
+        let weak = level.downgrade();
+        assert_eq!(weak.load(Ordering::Relaxed), Some(-1));
+        weak.upgrade().unwrap().store(5, Ordering::Relaxed);
+        assert_eq!(level.load(Ordering::Relaxed), 5);
+
+        drop(level);
+        assert!(weak.is_expired());
+        assert_eq!(weak.clone().load(Ordering::Relaxed), None);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::arcm::Arcm;
+use core::fmt::Debug;
+use std::sync::OnceLock;
+
+/// An Arcm that is created on first use, so shared state can be declared in a
+/// `static`. Requires the `std` feature.
+pub struct ArcmCell<T> {
+    cell: OnceLock<Arcm<T>>,
+}
+
+impl<T> ArcmCell<T> {
+    /// Creates an empty cell; usable in `static` and `const` contexts
+    pub const fn new() -> Self {
+        Self {
+            cell: OnceLock::new(),
+        }
+    }
+
+    /// Returns the Arcm, creating it from `f()` if this is the first call.
+    /// If several threads race here, `f` runs only once.
+    pub fn get_or_init<F>(&self, f: F) -> &Arcm<T>
+    where
+        F: FnOnce() -> T,
+    {
+        self.cell.get_or_init(|| Arcm::new(f()))
+    }
+
+    /// Returns the Arcm if it has been created
+    pub fn get(&self) -> Option<&Arcm<T>> {
+        self.cell.get()
+    }
+}
+
+impl<T> Default for ArcmCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for ArcmCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ArcmCell")
+            .field("cell", &self.cell)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    static SHARED: ArcmCell<Vec<usize>> = ArcmCell::new();
+
+    #[test]
+    fn test_static_init() {
+        let handles: Vec<_> = (0..4)
+            .map(|i| thread::spawn(move || SHARED.get_or_init(Vec::new).modify(|v| v.push(i))))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut values = SHARED.get().unwrap().value();
+        values.sort();
+        assert_eq!(values, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_get_before_init() {
+        let cell = ArcmCell::new();
+        assert!(cell.get().is_none());
+        assert_eq!(cell.get_or_init(|| 1).value(), 1);
+        assert_eq!(cell.get_or_init(|| panic!("initialized twice")).value(), 1);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::arcm::Arcm;
+use alloc::collections::VecDeque;
+
+/// Bounded-history operations on a shared `VecDeque`, each taking the lock
+/// once.
+///
+/// Mutations go through [`Arcm::modify`], so they notify subscribers and bump
+/// the generation like any other change.
+pub trait ArcmDequeExt<T> {
+    /// Pushes `item` to the back, then evicts from the front until at most
+    /// `max_len` items remain. Returns the oldest evicted item, if any; a
+    /// deque that was already over the cap loses the rest silently.
+    fn push_capped(&self, item: T, max_len: usize) -> Option<T>;
+}
+
+impl<T> ArcmDequeExt<T> for Arcm<VecDeque<T>> {
+    fn push_capped(&self, item: T, max_len: usize) -> Option<T> {
+        self.modify(|items| {
+            items.push_back(item);
+            let excess = items.len().saturating_sub(max_len);
+            items.drain(..excess).next()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_capped() {
+        let history = Arcm::new(VecDeque::new());
+        assert_eq!(history.push_capped(1, 2), None);
+        assert_eq!(history.push_capped(2, 2), None);
+        assert_eq!(history.push_capped(3, 2), Some(1));
+        assert_eq!(history.value(), [2, 3]);
+
+        // A cap of zero evicts the item just pushed
+        assert_eq!(history.push_capped(4, 0), Some(2));
+        assert!(history.value().is_empty());
+
+        // Shrinking the cap trims the deque in one call
+        let history = Arcm::new(VecDeque::from([1, 2, 3, 4]));
+        assert_eq!(history.push_capped(5, 2), Some(1));
+        assert_eq!(history.value(), [4, 5]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_cap_holds_under_contention() {
+        let history = Arcm::new(VecDeque::new());
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                history.spawn_with(move |history| {
+                    for j in 0..100 {
+                        history.push_capped(i * 100 + j, 10);
+                        assert!(history.with(|h| h.len()) <= 10);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(history.with(|h| h.len()), 10);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::sync::{fmt_debug, lock, Mutex};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+/// A wrapper combining Arc and Mutex for shared mutable access to unsized
+/// values such as trait objects.
+/// Since a `dyn Trait` can't be cloned, access is borrow-based only.
+/// To share one across threads the trait object must be `Send`, e.g.
+/// `ArcmDyn<dyn Handler + Send>`.
+pub struct ArcmDyn<T: ?Sized> {
+    inner: Arc<Mutex<Box<T>>>,
+}
+
+impl<T: ?Sized> ArcmDyn<T> {
+    /// Creates a new ArcmDyn from a boxed value, e.g. `Box<dyn Handler>`
+    pub fn new(value: Box<T>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(value)),
+        }
+    }
+
+    /// Modifies the contained value using the provided closure
+    pub fn modify<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = lock(&self.inner);
+        f(&mut **guard)
+    }
+
+    /// Runs the provided closure with a reference to the contained value
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let guard = lock(&self.inner);
+        f(&**guard)
+    }
+}
+
+impl<T: ?Sized> Clone for ArcmDyn<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for ArcmDyn<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt_debug(f, "ArcmDyn", &self.inner)
+    }
+}
+
+impl<T: ?Sized> From<Box<T>> for ArcmDyn<T> {
+    fn from(value: Box<T>) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    trait Handler: Send {
+        fn handle(&mut self, event: &str);
+        fn handled(&self) -> usize;
+    }
+
+    struct Counter {
+        count: usize,
+    }
+
+    impl Handler for Counter {
+        fn handle(&mut self, _event: &str) {
+            self.count += 1;
+        }
+
+        fn handled(&self) -> usize {
+            self.count
+        }
+    }
+
+    #[test]
+    fn test_trait_object() {
+        let handler: ArcmDyn<dyn Handler> = ArcmDyn::new(Box::new(Counter { count: 0 }));
+        let handler2 = handler.clone();
+
+        handler.modify(|h| h.handle("click"));
+        handler2.modify(|h| h.handle("scroll"));
+        assert_eq!(handler.with(|h| h.handled()), 2);
+    }
+
+    #[test]
+    fn test_unsized_slice_and_str() {
+        let numbers: ArcmDyn<[i32]> = ArcmDyn::from(vec![1, 2, 3].into_boxed_slice());
+        numbers.modify(|n| n[0] = 10);
+        assert_eq!(numbers.with(|n| n.iter().sum::<i32>()), 15);
+
+        let name: ArcmDyn<str> = ArcmDyn::from(Box::<str>::from("hello"));
+        assert_eq!(name.with(|s| s.len()), 5);
+        assert_eq!(format!("{:?}", name), format!("{:?}", name.clone()));
+    }
+
+    #[test]
+    fn test_threads() {
+        let handler: ArcmDyn<dyn Handler> = ArcmDyn::new(Box::new(Counter { count: 0 }));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        handler.modify(|h| h.handle("tick"));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(handler.with(|h| h.handled()), 400);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::arcm::Arcm;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use std::collections::HashMap;
+
+/// Map operations on a shared `HashMap`, each taking the lock once. Requires
+/// the `std` feature.
+///
+/// Mutations notify subscribers and bump the generation like any other change;
+/// removing a missing key changes nothing and does neither.
+pub trait ArcmMapExt<K, V> {
+    /// Inserts a key-value pair, returning the previous value for the key
+    fn insert(&self, key: K, value: V) -> Option<V>;
+
+    /// Removes a key, returning its value if it was present
+    fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+
+    /// Returns a copy of the value for a key
+    fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+
+    /// Returns true if the map contains the key
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized;
+}
+
+impl<K, V> ArcmMapExt<K, V> for Arcm<HashMap<K, V>>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        self.modify(|map| map.insert(key, value))
+    }
+
+    fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut map = self.guard();
+        let value = map.remove(key);
+        if value.is_some() {
+            self.changed(&map);
+        }
+        value
+    }
+
+    fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.with(|map| map.get(key).cloned())
+    }
+
+    fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.with(|map| map.contains_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_operations() {
+        let sessions: Arcm<HashMap<String, u32>> = Arcm::new(HashMap::new());
+        assert_eq!(sessions.insert(String::from("alice"), 1), None);
+        assert_eq!(sessions.insert(String::from("alice"), 2), Some(1));
+
+        assert!(sessions.contains_key("alice"));
+        assert_eq!(sessions.get_cloned("alice"), Some(2));
+        assert_eq!(sessions.get_cloned("bob"), None);
+
+        assert_eq!(sessions.remove("alice"), Some(2));
+        assert!(!sessions.contains_key("alice"));
+        assert_eq!(sessions.generation(), 3);
+    }
+
+    #[test]
+    fn test_remove_missing_key() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let sessions = Arcm::new(HashMap::from([(String::from("alice"), 1)]));
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&notified);
+        sessions.subscribe(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(sessions.remove("bob"), None);
+        assert_eq!(sessions.generation(), 0);
+        assert_eq!(notified.load(Ordering::SeqCst), 0);
+
+        assert_eq!(sessions.remove("alice"), Some(1));
+        assert_eq!(sessions.generation(), 1);
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_concurrent_inserts() {
+        let map = Arcm::new(HashMap::new());
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                map.spawn_with(move |map| {
+                    map.insert(i, i * 10);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(map.with(|m| m.len()), 8);
+        assert_eq!(map.get_cloned(&7), Some(70));
+    }
+}
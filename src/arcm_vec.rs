@@ -0,0 +1,88 @@
+use crate::arcm::Arcm;
+use alloc::vec::Vec;
+
+/// Vector operations on a shared `Vec`, each taking the lock once.
+///
+/// Mutations notify subscribers and bump the generation like any other change;
+/// popping an empty vector changes nothing and does neither.
+pub trait ArcmVecExt<T> {
+    /// Appends an item to the end
+    fn push(&self, item: T);
+
+    /// Removes and returns the last item
+    fn pop(&self) -> Option<T>;
+
+    /// Returns the number of items
+    fn len(&self) -> usize;
+
+    /// Returns true if there are no items
+    fn is_empty(&self) -> bool;
+
+    /// Returns a copy of the item at `index`
+    fn get_cloned(&self, index: usize) -> Option<T>;
+}
+
+impl<T: Clone> ArcmVecExt<T> for Arcm<Vec<T>> {
+    fn push(&self, item: T) {
+        self.modify(|items| items.push(item));
+    }
+
+    fn pop(&self) -> Option<T> {
+        let mut items = self.guard();
+        let item = items.pop();
+        if item.is_some() {
+            self.changed(&items);
+        }
+        item
+    }
+
+    fn len(&self) -> usize {
+        self.with(|items| items.len())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.with(|items| items.is_empty())
+    }
+
+    fn get_cloned(&self, index: usize) -> Option<T> {
+        self.with(|items| items.get(index).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec_operations() {
+        let queue = Arcm::new(Vec::new());
+        assert!(queue.is_empty());
+
+        queue.push("a");
+        queue.push("b");
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.get_cloned(0), Some("a"));
+        assert_eq!(queue.get_cloned(2), None);
+
+        assert_eq!(queue.pop(), Some("b"));
+        assert_eq!(queue.pop(), Some("a"));
+        assert_eq!(queue.generation(), 4);
+
+        // An empty pop isn't a modification
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.generation(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_pushes() {
+        let queue = Arcm::new(Vec::new());
+        let handles: Vec<_> = (0..8)
+            .map(|i| queue.spawn_with(move |queue| queue.push(i)))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(queue.len(), 8);
+    }
+}
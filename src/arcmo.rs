@@ -1,24 +1,59 @@
-use std::fmt::Debug;
-use std::sync::{Arc, Mutex, Weak};
+use crate::arcm::Arcm;
+use crate::error::PoisonError;
+use crate::sync::{
+    fmt_debug, is_poisoned, lock, lock_checked, lock_pair, lock_traced, try_lock, Generation,
+    Mutex, MutexGuard,
+};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
 
 /// A wrapper combining Arc and Mutex for convenient shared mutable access to optional values
-/// Only works with types that implement Clone
-pub struct Arcmo<T: Clone> {
-    inner: Arc<Mutex<Option<T>>>,
+/// Reading the value out with `value` requires `T: Clone`; modifying it in
+/// place, `take` and `replace` work for any type.
+///
+/// Like `Arc<Mutex<Option<T>>>`, an Arcmo is `Send` and `Sync` only when
+/// `T: Send`.
+pub struct Arcmo<T> {
+    inner: Arc<Slot<T>>,
 }
 
-impl<T: Clone> Arcmo<T> {
+/// The shared allocation behind an Arcmo and its weak references
+struct Slot<T> {
+    value: Mutex<Option<T>>,
+    generation: Generation,
+}
+
+impl<T> Slot<T> {
+    fn new(value: Option<T>) -> Self {
+        Self {
+            value: Mutex::new(value),
+            generation: Generation::new(),
+        }
+    }
+
+    /// Bumps the generation, called while the value lock is held
+    fn changed(&self) {
+        self.generation.bump();
+    }
+}
+
+impl<T> Arcmo<T> {
     /// Creates a new empty Arcmo
     pub fn none() -> Self {
         Self {
-            inner: Arc::new(Mutex::new(None)),
+            inner: Arc::new(Slot::new(None)),
         }
     }
 
     /// Creates a new Arcmo containing Some(value)
     pub fn some(value: T) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(Some(value))),
+            inner: Arc::new(Slot::new(Some(value))),
         }
     }
 
@@ -30,76 +65,625 @@ impl<T: Clone> Arcmo<T> {
         T: Default,
         F: FnOnce(&mut T) -> R,
     {
-        let mut guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (_span, mut guard) = lock_traced(&self.inner.value, "Arcmo::modify");
+        let result = modify_or_default(&mut guard, f);
+        self.inner.changed();
+        result
+    }
 
-        match &mut *guard {
-            Some(value) => f(value),
-            None => {
-                let mut value = T::default();
-                let result = f(&mut value);
-                *guard = Some(value);
-                result
-            }
-        }
+    /// Like [`Arcmo::modify`], but also returns true if the value was missing
+    /// and this call created it, so one-time setup can run exactly once
+    pub fn modify_tracked<F, R>(&self, f: F) -> (R, bool)
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = lock(&self.inner.value);
+        let created = guard.is_none();
+        let result = modify_or_default(&mut guard, f);
+        self.inner.changed();
+        (result, created)
+    }
+
+    /// Like [`Arcmo::modify`], but creates the missing value with `init`
+    /// instead of requiring `T: Default`. `init` isn't called if a value exists.
+    pub fn modify_or<F, G, R>(&self, init: G, f: F) -> R
+    where
+        G: FnOnce() -> T,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = lock(&self.inner.value);
+        let result = f(guard.get_or_insert_with(init));
+        self.inner.changed();
+        result
     }
 
     /// Sets the value to None and returns the previous value if it existed
     pub fn take(&self) -> Option<T> {
-        let mut guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut guard = lock(&self.inner.value);
+        self.inner.changed();
         guard.take()
     }
 
+    /// Takes the contained value, or creates one with `f` if there is none.
+    /// The slot is left empty either way; `f` runs after the lock is released.
+    pub fn take_or_insert_with<F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let taken = self.take();
+        taken.unwrap_or_else(f)
+    }
+
+    /// Sets the value to `Some(T::default())`, dropping the previous value if
+    /// it existed
+    pub fn reset(&self)
+    where
+        T: Default,
+    {
+        self.set(T::default());
+    }
+
+    /// Sets the value to `Some(T::default())` and returns the previous value
+    /// if it existed
+    pub fn replace_with_default(&self) -> Option<T>
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Takes the contained value, leaving `T::default()` in its place so the
+    /// slot stays `Some`, e.g. to swap out a batch while producers keep
+    /// pushing. Returns `T::default()` and leaves the slot empty if it was None.
+    pub fn drain_collection(&self) -> T
+    where
+        T: Default,
+    {
+        let mut guard = lock(&self.inner.value);
+        self.inner.changed();
+        guard.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    /// Sets the value to None, dropping the previous value if it existed
+    pub fn clear(&self) {
+        self.set_option(None);
+    }
+
     /// Sets the value to Some(value) and returns the previous value if it existed
     pub fn replace(&self, value: T) -> Option<T> {
-        let mut guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (_span, mut guard) = lock_traced(&self.inner.value, "Arcmo::replace");
+        self.inner.changed();
         guard.replace(value)
     }
 
-    /// Returns a copy of the contained value if it exists
-    pub fn value(&self) -> Option<T> {
-        let guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
-        guard.clone()
+    /// Sets the value to Some(value), dropping the previous value if it existed
+    pub fn set(&self, value: T) {
+        self.set_option(Some(value));
+    }
+
+    /// Installs `value` as is, whether Some or None, and returns the previous
+    /// value if it existed
+    pub fn set_option(&self, value: Option<T>) -> Option<T> {
+        let mut guard = lock(&self.inner.value);
+        self.inner.changed();
+        core::mem::replace(&mut *guard, value)
+    }
+
+    /// Returns true if the contained value is Some and equal to `value`,
+    /// comparing in place without cloning
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        lock(&self.inner.value).as_ref() == Some(value)
+    }
+
+    /// Applies `f` to a reference to the contained value, or returns `default`
+    /// if there is none. Avoids cloning the value just to project from it.
+    pub fn map_or<U, F>(&self, default: U, f: F) -> U
+    where
+        F: FnOnce(&T) -> U,
+    {
+        lock(&self.inner.value).as_ref().map_or(default, f)
+    }
+
+    /// Applies `f` to a reference to the contained value, or computes a
+    /// fallback with `default` if there is none
+    pub fn map_or_else<U, D, F>(&self, default: D, f: F) -> U
+    where
+        D: FnOnce() -> U,
+        F: FnOnce(&T) -> U,
+    {
+        let guard = lock(&self.inner.value);
+        match guard.as_ref() {
+            Some(value) => f(value),
+            None => {
+                // Released before calling `default` so the fallback can use this Arcmo
+                drop(guard);
+                default()
+            }
+        }
     }
 
     /// Returns true if the contained value is Some
     pub fn is_some(&self) -> bool {
-        let guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let guard = lock(&self.inner.value);
         guard.is_some()
     }
 
     /// Returns true if the contained value is None
     pub fn is_none(&self) -> bool {
-        let guard = self
-            .inner
-            .lock()
-            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let guard = lock(&self.inner.value);
         guard.is_none()
     }
 
+    /// Returns true if a thread ever panicked while holding the lock. The
+    /// value is still recovered transparently; this only reports that it
+    /// happened. Always false with the `parking_lot` and `spin` features.
+    pub fn is_poisoned(&self) -> bool {
+        is_poisoned(&self.inner.value)
+    }
+
+    /// Returns the number of modifications made so far through any handle.
+    ///
+    /// Every call that may change the value counts, even if it leaves the
+    /// value as it was; conditional setters such as `replace_if` count only
+    /// when they write. The counter is bumped under the same lock as the
+    /// mutation.
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.get()
+    }
+
+    /// Poisons the lock, as if a thread had panicked while holding it, so code
+    /// handling poisoning can be tested. A no-op with the `parking_lot` and
+    /// `spin` features, which never poison. Requires the `test-util` feature.
+    #[cfg(all(feature = "std", any(test, feature = "test-util")))]
+    pub fn force_poison(&self)
+    where
+        T: Send,
+    {
+        crate::sync::force_poison(&self.inner.value);
+    }
+
+    /// Sets the value to Some(value) only if it is currently None
+    pub fn or_insert(&self, value: T) {
+        self.replace_if_none(value);
+    }
+
+    /// Sets the value to Some(value) only if it is currently None. Returns
+    /// true if this call installed the value, false if one was already present
+    pub fn replace_if_none(&self, value: T) -> bool {
+        let mut guard = lock(&self.inner.value);
+        if guard.is_some() {
+            return false;
+        }
+        *guard = Some(value);
+        self.inner.changed();
+        true
+    }
+
+    /// Sets the value to Some(new) only if `predicate` accepts the current
+    /// contents. The check and the swap happen under a single lock
+    /// acquisition, so competing writers can't interleave between them.
+    /// Returns true if the value was replaced.
+    pub fn replace_if<F>(&self, new: T, predicate: F) -> bool
+    where
+        F: FnOnce(Option<&T>) -> bool,
+    {
+        let mut guard = lock(&self.inner.value);
+        if !predicate(guard.as_ref()) {
+            return false;
+        }
+        *guard = Some(new);
+        self.inner.changed();
+        true
+    }
+
+    /// Sets the value to Some(value) unless it already holds an equal value.
+    /// Returns the previous value if the contents changed; returns None both
+    /// for a no-op and when replacing None. The comparison and the swap happen
+    /// under a single lock acquisition, and a no-op leaves the generation
+    /// untouched.
+    pub fn set_dedup(&self, value: T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let mut guard = lock(&self.inner.value);
+        if guard.as_ref() == Some(&value) {
+            return None;
+        }
+        self.inner.changed();
+        guard.replace(value)
+    }
+
+    /// Sets the value to Some(f()) only if it is currently None; `f` isn't
+    /// called otherwise
+    pub fn or_insert_with<F>(&self, f: F)
+    where
+        F: FnOnce() -> T,
+    {
+        let mut guard = lock(&self.inner.value);
+        if guard.is_none() {
+            *guard = Some(f());
+            self.inner.changed();
+        }
+    }
+
+    /// Inserts `default()` if there is no value, then runs `edit` against the
+    /// value, all under a single lock acquisition. Returns the result of `edit`.
+    pub fn entry<F, G, R>(&self, default: F, edit: G) -> R
+    where
+        F: FnOnce() -> T,
+        G: FnOnce(&mut T) -> R,
+    {
+        let mut guard = lock(&self.inner.value);
+        let result = edit(guard.get_or_insert_with(default));
+        self.inner.changed();
+        result
+    }
+
+    /// Locks the value, inserting `f()` first if there is none, and returns a
+    /// guard for several operations on it under a single lock acquisition.
+    /// The value stays in place when the guard is dropped.
+    pub fn lock_or_insert_with<F>(&self, f: F) -> ArcmoGuard<'_, T>
+    where
+        F: FnOnce() -> T,
+    {
+        let mut guard = lock(&self.inner.value);
+        guard.get_or_insert_with(f);
+        // Counted up front; nobody can observe the generation until the
+        // guard is released
+        self.inner.changed();
+        ArcmoGuard {
+            guard,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Clears the value if it exists and `predicate` returns false for it.
+    /// The check and the clear happen under a single lock acquisition.
+    pub fn filter<F>(&self, predicate: F)
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let mut guard = lock(&self.inner.value);
+        if let Some(value) = &*guard {
+            if !predicate(value) {
+                *guard = None;
+                self.inner.changed();
+            }
+        }
+    }
+
+    /// Like [`Arcmo::modify`], but returns an error instead of recovering if
+    /// the lock was poisoned by a panicking thread. The closure is not run in
+    /// that case.
+    pub fn try_modify_checked<F, R>(&self, f: F) -> Result<R, PoisonError>
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = lock_checked(&self.inner.value)?;
+        let result = modify_or_default(&mut guard, f);
+        self.inner.changed();
+        Ok(result)
+    }
+
+    /// Like [`Arcmo::replace`], but returns an error if the lock was poisoned.
+    /// The new value is dropped in that case.
+    pub fn replace_checked(&self, value: T) -> Result<Option<T>, PoisonError> {
+        let mut guard = lock_checked(&self.inner.value)?;
+        self.inner.changed();
+        Ok(guard.replace(value))
+    }
+
+    /// Runs `f` with a reference to the contained value without cloning it.
+    /// Returns None without calling `f` if there is no value.
+    ///
+    /// This is the way to read a small part of a large or non-`Clone` value:
+    /// the existence check and the projection happen under one lock.
+    pub fn with<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        lock(&self.inner.value).as_ref().map(f)
+    }
+
+    /// Like [`Option::and_then`] on a reference to the contained value:
+    /// returns None if there is no value, otherwise the result of `f`
+    pub fn and_then<F, U>(&self, f: F) -> Option<U>
+    where
+        F: FnOnce(&T) -> Option<U>,
+    {
+        lock(&self.inner.value).as_ref().and_then(f)
+    }
+
+    /// Runs `f` with a reference to the contained value, if any, and returns
+    /// `self` for chaining. The lock is released before this returns.
+    pub fn inspect<F>(&self, f: F) -> &Self
+    where
+        F: FnOnce(Option<&T>),
+    {
+        f(lock(&self.inner.value).as_ref());
+        self
+    }
+
+    /// Like [`Arcmo::inspect`], but runs `f` only if there is a value
+    pub fn inspect_present<F>(&self, f: F) -> &Self
+    where
+        F: FnOnce(&T),
+    {
+        if let Some(value) = lock(&self.inner.value).as_ref() {
+            f(value);
+        }
+        self
+    }
+
+    /// Runs `f` only if there is no value, and returns `self` for chaining.
+    /// The lock is released before `f` runs.
+    pub fn inspect_absent<F>(&self, f: F) -> &Self
+    where
+        F: FnOnce(),
+    {
+        if self.is_none() {
+            f();
+        }
+        self
+    }
+
+    /// Returns a `Display` adapter that prints the contained value, or
+    /// `placeholder` when there is none. Like `Display`, it prints `<locked>`
+    /// rather than blocking while the lock is held.
+    pub fn display_or<'a>(&'a self, placeholder: &'a str) -> DisplayOr<'a, T> {
+        DisplayOr {
+            arcmo: self,
+            placeholder,
+        }
+    }
+
     /// Returns a weak reference to the contained value
     pub fn downgrade(&self) -> WeakArcmo<T> {
         WeakArcmo {
             inner: Arc::downgrade(&self.inner),
         }
     }
+
+    /// Returns a [`CachedArcmo`] that keeps a copy of the value and clones it
+    /// again only after it has been modified
+    pub fn cached(&self) -> CachedArcmo<T> {
+        CachedArcmo {
+            arcmo: self.clone(),
+            cached: None,
+        }
+    }
+
+    /// Returns true if both handles share the same storage, as clones do
+    pub fn shares_with(&self, other: &Arcmo<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns the `(strong, weak)` reference counts of the shared storage,
+    /// for assertions about sharing in tests. Other threads may change the
+    /// counts at any time, so don't base decisions on them.
+    pub fn debug_refcounts(&self) -> (usize, usize) {
+        (Arc::strong_count(&self.inner), Arc::weak_count(&self.inner))
+    }
+
+    /// Spawns a thread that runs `f` with a clone of this handle. Requires the
+    /// `std` feature.
+    #[cfg(feature = "std")]
+    pub fn spawn_with<F>(&self, f: F) -> std::thread::JoinHandle<()>
+    where
+        F: FnOnce(Arcmo<T>) + Send + 'static,
+        T: Send + 'static,
+    {
+        let handle = self.clone();
+        std::thread::spawn(move || f(handle))
+    }
 }
 
-impl<T: Clone> Clone for Arcmo<T> {
+impl<T: Clone> Arcmo<T> {
+    /// Returns a copy of the contained value if it exists
+    pub fn value(&self) -> Option<T> {
+        let guard = lock(&self.inner.value);
+        guard.clone()
+    }
+
+    /// Returns a copy of the contained value, or `default` if there is none
+    pub fn value_or(&self, default: T) -> T {
+        lock(&self.inner.value).clone().unwrap_or(default)
+    }
+
+    /// Returns a copy of the contained value, or computes one with `f` if there is none
+    pub fn value_or_else<F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        // Released before calling `f` so the fallback can use this Arcmo
+        let value = lock(&self.inner.value).clone();
+        value.unwrap_or_else(f)
+    }
+
+    /// Returns a copy of the contained value, or `T::default()` if there is none
+    pub fn value_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        lock(&self.inner.value).clone().unwrap_or_default()
+    }
+
+    /// Sets the value to Some(new) and returns the previous value alongside a
+    /// copy of the new one, both taken under the same lock
+    pub fn swap_value(&self, new: T) -> (Option<T>, T) {
+        let mut guard = lock(&self.inner.value);
+        self.inner.changed();
+        let current = new.clone();
+        let previous = guard.replace(new);
+        (previous, current)
+    }
+
+    /// Same as [`Arcmo::value_or`], named to read like `Option::or` in
+    /// fallback chains
+    pub fn or(&self, other: T) -> T {
+        self.value_or(other)
+    }
+
+    /// Same as [`Arcmo::value_or_else`], named to read like `Option::or_else`
+    pub fn or_else<F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.value_or_else(f)
+    }
+
+    /// Returns a copy of this Arcmo's value, or of `other`'s if this one is
+    /// empty. The locks are taken one after the other, never together, so
+    /// `other` may be any Arcmo, including this one.
+    pub fn or_arcmo(&self, other: &Arcmo<T>) -> Option<T> {
+        self.value().or_else(|| other.value())
+    }
+
+    /// Returns a copy of the contained value, first inserting `f()?` if there
+    /// is none. On error the slot stays empty and the error is returned.
+    ///
+    /// `f` runs under the lock, so racing callers wait for it instead of
+    /// running it too; after a failure the next caller tries again.
+    pub fn get_or_try_insert_with<F, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        let mut guard = lock(&self.inner.value);
+        if let Some(value) = &*guard {
+            return Ok(value.clone());
+        }
+        let value = f()?;
+        self.inner.changed();
+        Ok(guard.insert(value).clone())
+    }
+
+    /// Returns a copy of the contained value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no value, naming the contained type.
+    #[track_caller]
+    pub fn unwrap(&self) -> T {
+        match self.value() {
+            Some(value) => value,
+            None => panic!(
+                "called `Arcmo::unwrap()` on an empty Arcmo<{}>",
+                core::any::type_name::<T>()
+            ),
+        }
+    }
+
+    /// Returns a copy of the contained value.
+    ///
+    /// # Panics
+    ///
+    /// Panics with `msg` and the contained type if there is no value.
+    #[track_caller]
+    pub fn expect(&self, msg: &str) -> T {
+        match self.value() {
+            Some(value) => value,
+            None => panic!("{} (empty Arcmo<{}>)", msg, core::any::type_name::<T>()),
+        }
+    }
+
+    /// Returns an iterator over a snapshot of the contained value, yielding
+    /// it once if present and nothing otherwise
+    pub fn iter_value(&self) -> core::option::IntoIter<T> {
+        self.value().into_iter()
+    }
+
+    /// Like [`Arcmo::value`], but returns an error if the lock was poisoned
+    pub fn value_checked(&self) -> Result<Option<T>, PoisonError> {
+        Ok(lock_checked(&self.inner.value)?.clone())
+    }
+
+    /// Converts into an Arcm holding a snapshot of the current value, or
+    /// returns None if there is no value.
+    ///
+    /// The result is a new, independent allocation: changes made through it
+    /// are not seen by other clones of this Arcmo, and vice versa.
+    pub fn into_arcm(self) -> Option<Arcm<T>> {
+        self.value().map(Arcm::new)
+    }
+}
+
+impl<T: Clone> Arcmo<Option<T>> {
+    /// Collapses a nested option into an Arcmo holding a snapshot of the
+    /// inner value: `Some(Some(v))` becomes `Some(v)`, anything else `None`.
+    ///
+    /// Like [`Arcmo::into_arcm`], the result is a new, independent allocation.
+    pub fn flatten(self) -> Arcmo<T> {
+        Arcmo {
+            inner: Arc::new(Slot::new(self.value().flatten())),
+        }
+    }
+}
+
+/// A held lock on an Arcmo known to contain a value, returned by
+/// [`Arcmo::lock_or_insert_with`]
+pub struct ArcmoGuard<'a, T> {
+    // Always Some; only `lock_or_insert_with` constructs this
+    guard: MutexGuard<'a, Option<T>>,
+    // Like MutexGuard, the lock must be released on the thread that took it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<T> Deref for ArcmoGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match &*self.guard {
+            Some(value) => value,
+            None => unreachable!("ArcmoGuard always holds a value"),
+        }
+    }
+}
+
+impl<T> DerefMut for ArcmoGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match &mut *self.guard {
+            Some(value) => value,
+            None => unreachable!("ArcmoGuard always holds a value"),
+        }
+    }
+}
+
+impl<T: Debug> Debug for ArcmoGuard<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArcmoGuard").field(&**self).finish()
+    }
+}
+
+/// Runs `f` against the contained value, inserting `T::default()` first if empty
+fn modify_or_default<T, F, R>(slot: &mut Option<T>, f: F) -> R
+where
+    T: Default,
+    F: FnOnce(&mut T) -> R,
+{
+    match slot {
+        Some(value) => f(value),
+        None => {
+            let mut value = T::default();
+            let result = f(&mut value);
+            *slot = Some(value);
+            result
+        }
+    }
+}
+
+// Crate-internal access for the free functions that lock several handles at once
+impl<T> Arcmo<T> {
+    pub(crate) fn mutex(&self) -> &Mutex<Option<T>> {
+        &self.inner.value
+    }
+}
+
+impl<T> Clone for Arcmo<T> {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
@@ -107,85 +691,311 @@ impl<T: Clone> Clone for Arcmo<T> {
     }
 }
 
-impl<T: Clone + Debug> Debug for Arcmo<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Arcmo").field("inner", &self.inner).finish()
+/// Prints `Arcmo(Some(value))` or `Arcmo(None)`. Never blocks: a locked
+/// Arcmo prints `Arcmo(<locked>)`.
+impl<T: Debug> Debug for Arcmo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt_debug(f, "Arcmo", &self.inner.value)
+    }
+}
+
+/// Prints the contained value, `None` when empty, or `<locked>` instead of
+/// blocking while the lock is held
+impl<T: Display> Display for Arcmo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.display_or("None").fmt(f)
+    }
+}
+
+/// A `Display` adapter for Arcmo with a custom placeholder, see [`Arcmo::display_or`]
+pub struct DisplayOr<'a, T> {
+    arcmo: &'a Arcmo<T>,
+    placeholder: &'a str,
+}
+
+impl<T: Display> Display for DisplayOr<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match try_lock(&self.arcmo.inner.value).as_deref() {
+            Some(Some(value)) => Display::fmt(value, f),
+            Some(None) => f.write_str(self.placeholder),
+            None => f.write_str("<locked>"),
+        }
+    }
+}
+
+/// Compares the contained options, locking both handles in a consistent order.
+/// Handles sharing an allocation are compared under a single lock.
+impl<T: PartialEq> PartialEq for Arcmo<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            let value = lock(&self.inner.value);
+            return PartialEq::eq(&*value, &*value);
+        }
+        let (a, b) = lock_pair(&self.inner.value, &other.inner.value);
+        *a == *b
+    }
+}
+
+impl<T: Eq> Eq for Arcmo<T> {}
+
+/// Orders by the contained options, so `None` sorts before any `Some`. Locks
+/// like [`PartialEq`].
+///
+/// Mutating an Arcmo while it sits in an ordered container such as a
+/// `BinaryHeap` or `BTreeMap` breaks that container's invariants; the ordering
+/// is only as stable as the values.
+impl<T: PartialOrd> PartialOrd for Arcmo<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            let value = lock(&self.inner.value);
+            return value.partial_cmp(&value);
+        }
+        let (a, b) = lock_pair(&self.inner.value, &other.inner.value);
+        (*a).partial_cmp(&*b)
+    }
+}
+
+impl<T: Ord> Ord for Arcmo<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return Ordering::Equal;
+        }
+        let (a, b) = lock_pair(&self.inner.value, &other.inner.value);
+        (*a).cmp(&*b)
+    }
+}
+
+/// Hashes the contained option.
+///
+/// The hash follows the value, so mutating an Arcmo while it is used as a
+/// key in a `HashMap` or `HashSet` leaves the collection inconsistent; keeping
+/// keys unchanged after insertion is the caller's responsibility.
+impl<T: Hash> Hash for Arcmo<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        lock(&self.inner.value).hash(state);
+    }
+}
+
+impl<T> Default for Arcmo<T> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// A weak reference wrapper for Arcmo
+pub struct WeakArcmo<T> {
+    inner: Weak<Slot<T>>,
+}
+
+impl<T> WeakArcmo<T> {
+    /// Attempts to modify the value if it exists and the original Arcmo still exists
+    pub fn modify<F, R>(&self, f: F) -> Option<R>
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        self.inner.upgrade().map(|arc| {
+            let mut guard = lock(&arc.value);
+            let result = modify_or_default(&mut guard, f);
+            arc.changed();
+            result
+        })
+    }
+
+    /// Returns true if both the original Arcmo exists and contains Some value
+    pub fn is_some(&self) -> bool {
+        self.inner
+            .upgrade()
+            .map(|arc| lock(&arc.value).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Returns true if either the original Arcmo is dropped or contains None
+    pub fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    /// Like [`Arcmo::is_poisoned`]; false if the original Arcmo is gone
+    pub fn is_poisoned(&self) -> bool {
+        self.inner
+            .upgrade()
+            .map(|inner| is_poisoned(&inner.value))
+            .unwrap_or(false)
+    }
+
+    /// Attempts to replace the value if the original Arcmo still exists
+    pub fn replace(&self, value: T) -> Option<Option<T>> {
+        self.inner.upgrade().map(|arc| {
+            let mut guard = lock(&arc.value);
+            arc.changed();
+            guard.replace(value)
+        })
+    }
+
+    /// Returns true if both weak references point to the same Arcmo allocation
+    pub fn ptr_eq(&self, other: &WeakArcmo<T>) -> bool {
+        Weak::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns true once every strong Arcmo has been dropped, without
+    /// upgrading
+    pub fn is_expired(&self) -> bool {
+        self.inner.strong_count() == 0
+    }
+
+    /// Upgrades once and runs `f` with the resulting strong handle, so several
+    /// operations see the Arcmo stay alive throughout. Returns None without
+    /// calling `f` if the original Arcmo is gone.
+    pub fn with_upgraded<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&Arcmo<T>) -> R,
+    {
+        self.inner.upgrade().map(|inner| f(&Arcmo { inner }))
+    }
+
+    /// Returns a [`CachedWeakArcmo`] for repeated reads through this reference
+    pub fn cached(&self) -> CachedWeakArcmo<T> {
+        CachedWeakArcmo {
+            weak: Weak::clone(&self.inner),
+            cached: None,
+        }
+    }
+}
+
+impl<T: Clone> WeakArcmo<T> {
+    /// Attempts to get a copy of the value if it exists and the original Arcmo still exists
+    pub fn value(&self) -> Option<T> {
+        self.inner
+            .upgrade()
+            .and_then(|arc| lock(&arc.value).clone())
+    }
+}
+
+/// A reading handle that keeps a copy of an Arcmo's value, returned by
+/// [`Arcmo::cached`]. For values that are expensive to clone, read often and
+/// written rarely.
+///
+/// Each read checks the Arcmo's generation without locking, and copies the
+/// value out of it only if it has been modified since the last read. Reads
+/// hand out a reference to the kept copy, so an unchanged value isn't cloned
+/// or locked at all.
+pub struct CachedArcmo<T> {
+    arcmo: Arcmo<T>,
+    // The generation the copy was taken at, and the copy
+    cached: Option<(u64, Option<T>)>,
+}
+
+impl<T: Clone> CachedArcmo<T> {
+    /// Returns the current value, refreshing the kept copy from the Arcmo
+    /// only if it changed since the last call
+    pub fn value(&mut self) -> Option<&T> {
+        let stale = match &self.cached {
+            Some((cached, _)) => *cached != self.arcmo.generation(),
+            None => true,
+        };
+        if stale {
+            // Re-read the generation under the lock so it matches the copy
+            let guard = lock(&self.arcmo.inner.value);
+            self.cached = Some((self.arcmo.generation(), guard.clone()));
+        }
+        self.cached.as_ref().and_then(|(_, value)| value.as_ref())
+    }
+}
+
+impl<T> CachedArcmo<T> {
+    /// Returns the Arcmo this reads from
+    pub fn arcmo(&self) -> &Arcmo<T> {
+        &self.arcmo
     }
 }
 
-impl<T: Clone + Default> Default for Arcmo<T> {
-    fn default() -> Self {
-        Self::none()
+impl<T: Debug> Debug for CachedArcmo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CachedArcmo")
+            .field("arcmo", &self.arcmo)
+            .field(
+                "generation",
+                &self.cached.as_ref().map(|(generation, _)| generation),
+            )
+            .finish()
     }
 }
 
-/// A weak reference wrapper for Arcmo
-pub struct WeakArcmo<T: Clone> {
-    inner: Weak<Mutex<Option<T>>>,
+/// A weak reference that keeps the upgraded Arc between reads, so reading in
+/// a tight loop doesn't pay for an upgrade each time.
+///
+/// The cached Arc keeps the allocation alive, so other weak references can
+/// still upgrade until this one next reads. Each read checks whether this
+/// cache is the last strong reference; if so it releases it and returns None,
+/// as it will from then on.
+pub struct CachedWeakArcmo<T> {
+    weak: Weak<Slot<T>>,
+    cached: Option<Arc<Slot<T>>>,
 }
 
-impl<T: Clone> WeakArcmo<T> {
-    /// Attempts to modify the value if it exists and the original Arcmo still exists
-    pub fn modify<F, R>(&self, f: F) -> Option<R>
-    where
-        T: Default,
-        F: FnOnce(&mut T) -> R,
-    {
-        self.inner.upgrade().map(|arc| {
-            let mut guard = arc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
-            match &mut *guard {
-                Some(value) => f(value),
-                None => {
-                    let mut value = T::default();
-                    let result = f(&mut value);
-                    *guard = Some(value);
-                    result
-                }
+impl<T> CachedWeakArcmo<T> {
+    /// Returns the cached Arc if some Arcmo still holds it, upgrading only
+    /// when nothing is cached
+    fn strong(&mut self) -> Option<&Mutex<Option<T>>> {
+        if let Some(arc) = &self.cached {
+            if Arc::strong_count(arc) == 1 {
+                self.cached = None;
             }
-        })
+        }
+        if self.cached.is_none() {
+            self.cached = self.weak.upgrade();
+        }
+        self.cached.as_deref().map(|slot| &slot.value)
     }
 
-    /// Attempts to get a copy of the value if it exists and the original Arcmo still exists
-    pub fn value(&self) -> Option<T> {
-        self.inner.upgrade().and_then(|arc| match arc.lock() {
-            Ok(guard) => guard.clone(),
-            Err(poisoned) => poisoned.into_inner().clone(),
-        })
+    /// Runs `f` with a reference to the value if it exists and the original
+    /// Arcmo still exists
+    pub fn with<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        lock(self.strong()?).as_ref().map(f)
     }
+}
 
-    /// Returns true if both the original Arcmo exists and contains Some value
-    pub fn is_some(&self) -> bool {
-        self.inner
-            .upgrade()
-            .map(|arc| match arc.lock() {
-                Ok(guard) => guard.is_some(),
-                Err(poisoned) => poisoned.into_inner().is_some(),
-            })
-            .unwrap_or(false)
+impl<T: Clone> CachedWeakArcmo<T> {
+    /// Attempts to get a copy of the value if it exists and the original Arcmo still exists
+    pub fn value(&mut self) -> Option<T> {
+        lock(self.strong()?).clone()
     }
+}
 
-    /// Returns true if either the original Arcmo is dropped or contains None
-    pub fn is_none(&self) -> bool {
-        !self.is_some()
+impl<T> Debug for CachedWeakArcmo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CachedWeakArcmo")
+            .field("inner", &self.weak)
+            .field("cached", &self.cached.is_some())
+            .finish()
     }
+}
 
-    /// Attempts to replace the value if the original Arcmo still exists
-    pub fn replace(&self, value: T) -> Option<Option<T>> {
-        self.inner.upgrade().map(|arc| {
-            let mut guard = arc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
-            std::mem::replace(&mut *guard, Some(value))
-        })
+impl<T> Clone for WeakArcmo<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Weak::clone(&self.inner),
+        }
     }
 }
 
-impl<T: Clone> Debug for WeakArcmo<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<T> Debug for WeakArcmo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("WeakArcmo")
             .field("inner", &self.inner)
             .finish()
     }
 }
 
+/// Removes every weak reference whose Arcmo has been dropped, without
+/// upgrading the live ones
+pub fn prune_weak_arcmo<T>(weaks: &mut Vec<WeakArcmo<T>>) {
+    weaks.retain(|weak| !weak.is_expired());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,6 +1098,537 @@ mod tests {
         assert_eq!(v.value(), Some(42));
     }
 
+    #[test]
+    fn test_reset() {
+        let slot = Arcmo::<Vec<i32>>::none();
+        slot.reset();
+        assert_eq!(slot.value(), Some(vec![]));
+
+        slot.set(vec![1, 2]);
+        assert_eq!(slot.replace_with_default(), Some(vec![1, 2]));
+        assert_eq!(slot.value(), Some(vec![]));
+
+        slot.clear();
+        assert_eq!(slot.replace_with_default(), None);
+        assert!(slot.is_some());
+    }
+
+    #[test]
+    fn test_drain_collection() {
+        let queue = Arcmo::some(vec![1, 2]);
+        assert_eq!(queue.drain_collection(), vec![1, 2]);
+        assert_eq!(queue.value(), Some(vec![]));
+
+        queue.modify(|q| q.push(3));
+        assert_eq!(queue.drain_collection(), vec![3]);
+
+        let empty = Arcmo::<Vec<i32>>::none();
+        assert_eq!(empty.drain_collection(), Vec::<i32>::new());
+        assert!(empty.is_none());
+    }
+
+    #[test]
+    fn test_take_or_insert_with() {
+        let pool = Arcmo::some(vec![1]);
+        assert_eq!(pool.take_or_insert_with(Vec::new), vec![1]);
+        assert!(pool.is_none());
+
+        // The fallback may use the Arcmo, since the lock is already released
+        assert_eq!(
+            pool.take_or_insert_with(|| vec![pool.is_some() as i32]),
+            vec![0]
+        );
+        assert!(pool.is_none());
+    }
+
+    #[test]
+    fn test_value_or() {
+        let v = Arcmo::some(42);
+        assert_eq!(v.value_or(0), 42);
+        assert_eq!(v.value_or_else(|| panic!("fallback computed for Some")), 42);
+        assert_eq!(v.value_or_default(), 42);
+
+        v.take();
+        assert_eq!(v.value_or(7), 7);
+        assert_eq!(v.value_or_else(|| 8), 8);
+        assert_eq!(v.value_or_default(), 0);
+
+        // Reading a fallback doesn't store it
+        assert!(v.is_none());
+    }
+
+    #[test]
+    fn test_generation() {
+        let v = Arcmo::none();
+        assert_eq!(v.generation(), 0);
+        v.set(1);
+        v.modify(|n| *n += 1);
+        assert_eq!(v.generation(), 2);
+
+        // Conditional setters count only when they write
+        assert!(!v.replace_if_none(5));
+        assert!(!v.replace_if(5, |_| false));
+        v.or_insert(5);
+        assert_eq!(v.generation(), 2);
+
+        v.downgrade().replace(3);
+        v.take();
+        assert_eq!(v.clone().generation(), 4);
+    }
+
+    #[test]
+    fn test_cached_arcmo() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+        #[derive(Debug, PartialEq)]
+        struct Expensive(u32);
+        impl Clone for Expensive {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::SeqCst);
+                Expensive(self.0)
+            }
+        }
+
+        let source = Arcmo::some(Expensive(1));
+        let mut reader = source.cached();
+        assert_eq!(reader.value(), Some(&Expensive(1)));
+        assert_eq!(CLONES.load(Ordering::SeqCst), 1);
+
+        // A hit neither clones nor needs the lock
+        let held = lock(&source.inner.value);
+        assert_eq!(reader.value(), Some(&Expensive(1)));
+        drop(held);
+        assert_eq!(CLONES.load(Ordering::SeqCst), 1);
+
+        // A refresh clones exactly once
+        source.modify_or(|| Expensive(0), |e| e.0 = 2);
+        assert_eq!(reader.value(), Some(&Expensive(2)));
+        assert_eq!(CLONES.load(Ordering::SeqCst), 2);
+
+        source.clear();
+        assert_eq!(reader.value(), None);
+        assert!(reader.arcmo().shares_with(&source));
+    }
+
+    #[test]
+    fn test_set_option() {
+        let nickname = Arcmo::none();
+        assert_eq!(nickname.set_option(Some("bob")), None);
+        assert_eq!(nickname.set_option(Some("rob")), Some("bob"));
+        assert_eq!(nickname.set_option(None), Some("rob"));
+        assert!(nickname.is_none());
+    }
+
+    #[test]
+    fn test_swap_value() {
+        let creds = Arcmo::none();
+        assert_eq!(
+            creds.swap_value(String::from("a")),
+            (None, String::from("a"))
+        );
+
+        let (old, current) = creds.swap_value(String::from("b"));
+        assert_eq!(old.as_deref(), Some("a"));
+        assert_eq!(current, "b");
+        assert_eq!(creds.value().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_or_fallback_chain() {
+        let primary = Arcmo::none();
+        let defaults = Arcmo::some(String::from("default"));
+        assert_eq!(primary.or(String::from("x")), "x");
+        assert_eq!(primary.or_else(|| String::from("y")), "y");
+        assert_eq!(primary.or_arcmo(&defaults).as_deref(), Some("default"));
+
+        primary.set(String::from("custom"));
+        assert_eq!(primary.or_arcmo(&defaults).as_deref(), Some("custom"));
+        assert_eq!(
+            primary.or_else(|| panic!("fallback computed for Some")),
+            "custom"
+        );
+
+        defaults.clear();
+        assert_eq!(defaults.or_arcmo(&defaults), None);
+    }
+
+    #[test]
+    fn test_map_or() {
+        let v = Arcmo::some(Settings {
+            enabled: true,
+            count: 3,
+            name: "large".to_string(),
+        });
+        assert_eq!(v.map_or(0, |s| s.count), 3);
+        assert_eq!(v.map_or_else(|| 0, |s| s.name.len()), 5);
+
+        v.take();
+        assert_eq!(v.map_or(0, |s| s.count), 0);
+        assert_eq!(v.map_or_else(|| v.map_or(7, |s| s.count), |s| s.count), 7);
+    }
+
+    #[test]
+    fn test_contains() {
+        let v = Arcmo::some("hello".to_string());
+        assert!(v.contains(&"hello".to_string()));
+        assert!(!v.contains(&"world".to_string()));
+
+        v.take();
+        assert!(!v.contains(&"hello".to_string()));
+    }
+
+    #[test]
+    fn test_or_insert() {
+        let v = Arcmo::none();
+        v.or_insert(1);
+        v.or_insert(2);
+        assert_eq!(v.value(), Some(1));
+
+        let v = Arcmo::none();
+        v.or_insert_with(|| 1);
+        v.or_insert_with(|| panic!("called while Some"));
+        assert_eq!(v.value(), Some(1));
+    }
+
+    #[test]
+    fn test_set_dedup() {
+        let state = Arcmo::none();
+        assert_eq!(state.set_dedup(String::from("idle")), None);
+        assert_eq!(state.generation(), 1);
+
+        assert_eq!(state.set_dedup(String::from("idle")), None);
+        assert_eq!(state.generation(), 1);
+
+        assert_eq!(
+            state.set_dedup(String::from("busy")),
+            Some(String::from("idle"))
+        );
+        assert_eq!(state.value().as_deref(), Some("busy"));
+        assert_eq!(state.generation(), 2);
+    }
+
+    #[test]
+    fn test_replace_if_none() {
+        let v = Arcmo::none();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let v = v.clone();
+                thread::spawn(move || v.replace_if_none(i))
+            })
+            .collect();
+        let winners = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|won| *won)
+            .count();
+        assert_eq!(winners, 1);
+        assert!(v.is_some());
+        assert!(!v.replace_if_none(100));
+        assert_ne!(v.value(), Some(100));
+    }
+
+    #[test]
+    fn test_replace_if_newer() {
+        let state = Arcmo::none();
+        let newer = |version: u32| {
+            move |cur: Option<&u32>| match cur {
+                Some(current) => version > *current,
+                None => true,
+            }
+        };
+
+        assert!(state.replace_if(3, newer(3)));
+        assert!(!state.replace_if(2, newer(2)));
+        assert!(!state.replace_if(3, newer(3)));
+        assert!(state.replace_if(5, newer(5)));
+        assert_eq!(state.value(), Some(5));
+    }
+
+    #[test]
+    fn test_get_or_try_insert_with() {
+        let conn = Arcmo::none();
+        assert_eq!(
+            conn.get_or_try_insert_with(|| Err("refused")),
+            Err("refused")
+        );
+        assert!(conn.is_none());
+
+        assert_eq!(conn.get_or_try_insert_with(|| Ok::<_, &str>(7)), Ok(7));
+        assert_eq!(
+            conn.get_or_try_insert_with(|| -> Result<i32, &str> { panic!("called while Some") }),
+            Ok(7)
+        );
+    }
+
+    #[test]
+    fn test_get_or_try_insert_with_contention() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let conn = Arcmo::none();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (conn, attempts) = (conn.clone(), Arc::clone(&attempts));
+                thread::spawn(move || {
+                    conn.get_or_try_insert_with(|| {
+                        attempts.fetch_add(1, Ordering::SeqCst);
+                        Ok::<_, ()>("connected")
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Ok("connected"));
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_unwrap_and_expect() {
+        let v = Arcmo::some(3);
+        assert_eq!(v.unwrap(), 3);
+        assert_eq!(v.expect("configured at startup"), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "called `Arcmo::unwrap()` on an empty Arcmo<i32>")]
+    fn test_unwrap_none() {
+        Arcmo::<i32>::none().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "configured at startup (empty Arcmo<alloc::string::String>)")]
+    fn test_expect_none() {
+        Arcmo::<String>::none().expect("configured at startup");
+    }
+
+    #[test]
+    fn test_iter_value() {
+        let present = Arcmo::some(2);
+        let absent = Arcmo::<i32>::none();
+
+        let all: Vec<i32> = [1]
+            .into_iter()
+            .chain(present.iter_value())
+            .chain(absent.iter_value())
+            .collect();
+        assert_eq!(all, vec![1, 2]);
+        assert_eq!(absent.iter_value().count(), 0);
+    }
+
+    #[test]
+    fn test_modify_or() {
+        struct Connection {
+            retries: u32,
+        }
+
+        let conn = Arcmo::none();
+        let retries = conn.modify_or(
+            || Connection { retries: 0 },
+            |c| {
+                c.retries += 1;
+                c.retries
+            },
+        );
+        assert_eq!(retries, 1);
+
+        let retries = conn.modify_or(
+            || panic!("called while Some"),
+            |c| {
+                c.retries += 1;
+                c.retries
+            },
+        );
+        assert_eq!(retries, 2);
+    }
+
+    #[test]
+    fn test_modify_tracked() {
+        let counter = Arcmo::<u32>::none();
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || counter.modify_tracked(|n| *n += 1).1)
+            })
+            .collect();
+        let created = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|first| *first)
+            .count();
+        assert_eq!(created, 1);
+        assert_eq!(counter.value(), Some(8));
+        assert_eq!(counter.modify_tracked(|n| *n), (8, false));
+    }
+
+    #[test]
+    fn test_lock_or_insert_with() {
+        let log = Arcmo::none();
+        {
+            let mut entries = log.lock_or_insert_with(Vec::new);
+            entries.push("open");
+            entries.push("write");
+            assert_eq!(format!("{:?}", entries), r#"ArcmoGuard(["open", "write"])"#);
+        }
+        log.lock_or_insert_with(|| panic!("called while Some"))
+            .push("close");
+        assert_eq!(log.value(), Some(vec!["open", "write", "close"]));
+    }
+
+    #[test]
+    fn test_entry() {
+        let events = Arcmo::<Vec<&str>>::none();
+
+        let len = events.entry(Vec::new, |e| {
+            e.push("started");
+            e.len()
+        });
+        assert_eq!(len, 1);
+
+        let len = events.entry(
+            || panic!("called while Some"),
+            |e| {
+                e.push("stopped");
+                e.len()
+            },
+        );
+        assert_eq!(len, 2);
+        assert_eq!(events.value(), Some(vec!["started", "stopped"]));
+    }
+
+    #[test]
+    fn test_filter() {
+        let v = Arcmo::some(42);
+
+        v.filter(|n| *n > 0);
+        assert_eq!(v.value(), Some(42));
+
+        v.filter(|n| *n > 100);
+        assert!(v.is_none());
+
+        // The predicate isn't called when empty
+        v.filter(|_| panic!("predicate called on None"));
+        assert!(v.is_none());
+    }
+
+    #[test]
+    fn test_non_clone_type() {
+        #[derive(Debug, Default, PartialEq)]
+        struct Handle(u32);
+
+        let v = Arcmo::none();
+        v.modify(|h: &mut Handle| h.0 += 1);
+        assert!(v.contains(&Handle(1)));
+
+        assert_eq!(v.replace(Handle(5)), Some(Handle(1)));
+        assert_eq!(v.take(), Some(Handle(5)));
+        v.set(Handle(7));
+        assert_eq!(v.downgrade().modify(|h| h.0), Some(7));
+    }
+
+    #[test]
+    fn test_with() {
+        let name = Arcmo::some(String::from("sovran"));
+        assert_eq!(name.with(|s| s.len()), Some(6));
+
+        name.clear();
+        assert_eq!(
+            name.with(|_| -> usize { panic!("called while None") }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_with_projects_non_clone_value() {
+        struct Large {
+            id: u32,
+            _payload: Vec<u8>,
+        }
+
+        let v = Arcmo::some(Large {
+            id: 7,
+            _payload: vec![0; 1024],
+        });
+        assert_eq!(v.with(|large| large.id), Some(7));
+        v.take();
+        assert_eq!(v.with(|large| large.id), None);
+    }
+
+    #[test]
+    fn test_and_then() {
+        use std::collections::HashMap;
+
+        let cache = Arcmo::some(HashMap::from([("a", 1)]));
+        assert_eq!(cache.and_then(|c| c.get("a").copied()), Some(1));
+        assert_eq!(cache.and_then(|c| c.get("b").copied()), None);
+
+        cache.clear();
+        assert_eq!(cache.and_then(|c| c.get("a").copied()), None);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let v = Arcmo::<i32>::none();
+        let mut seen = Vec::new();
+
+        v.inspect(|n| seen.push(n.copied())).modify(|n| *n = 42);
+        v.inspect(|n| seen.push(n.copied()));
+        assert_eq!(seen, vec![None, Some(42)]);
+    }
+
+    #[test]
+    fn test_inspect_present_and_absent() {
+        let cache = Arcmo::none();
+        let (mut hits, mut misses) = (0, 0);
+
+        cache
+            .inspect_absent(|| misses += 1)
+            .inspect_present(|_| hits += 1);
+        cache.set("value");
+        cache
+            .inspect_absent(|| misses += 1)
+            .inspect_present(|v| hits += v.len());
+        assert_eq!((hits, misses), (5, 1));
+    }
+
+    #[test]
+    fn test_clear() {
+        let v = Arcmo::some(42);
+        let v2 = v.clone();
+        v.clear();
+        assert!(v2.is_none());
+
+        // Clearing an empty Arcmo is a no-op
+        v.clear();
+        assert!(v.is_none());
+    }
+
+    #[test]
+    fn test_into_arcm() {
+        let v = Arcmo::some(42);
+        let other = v.clone();
+
+        let arcm = v.into_arcm().unwrap();
+        assert_eq!(arcm.value(), 42);
+
+        // The conversion doesn't share storage with the original
+        arcm.set(100);
+        assert_eq!(other.value(), Some(42));
+
+        assert!(Arcmo::<i32>::none().into_arcm().is_none());
+    }
+
+    #[test]
+    fn test_set() {
+        let v = Arcmo::none();
+        v.set(1);
+        assert_eq!(v.value(), Some(1));
+        v.set(2);
+        assert_eq!(v.value(), Some(2));
+    }
+
     #[test]
     fn test_multiple_references() {
         let v1 = Arcmo::some(1);
@@ -323,6 +1664,158 @@ mod tests {
         assert_eq!(result.name, "");
     }
 
+    #[test]
+    fn test_display() {
+        let v = Arcmo::some(42);
+        assert_eq!(v.to_string(), "42");
+        assert_eq!(v.display_or("<unset>").to_string(), "42");
+
+        v.take();
+        assert_eq!(v.to_string(), "None");
+        assert_eq!(v.display_or("<unset>").to_string(), "<unset>");
+        assert_eq!(v.modify(|_| v.to_string()), "<locked>");
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_eq_and_hash() {
+        use std::collections::HashSet;
+
+        let a = Arcmo::some(1);
+        assert_eq!(a, Arcmo::some(1));
+        assert_eq!(a, a.clone());
+        assert_ne!(a, Arcmo::none());
+        assert_eq!(Arcmo::<i32>::none(), Arcmo::none());
+
+        let mut set = HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&Arcmo::some(1)));
+        assert!(!set.contains(&Arcmo::none()));
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut items = [Arcmo::some(2), Arcmo::none(), Arcmo::some(1)];
+        items.sort();
+        let sorted: Vec<_> = items.iter().map(|v| v.value()).collect();
+        assert_eq!(sorted, vec![None, Some(1), Some(2)]);
+        assert_eq!(items[0].cmp(&items[0].clone()), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_debug_shows_value() {
+        let v = Arcmo::some(42);
+        assert_eq!(format!("{:?}", v), "Arcmo(Some(42))");
+        assert_eq!(format!("{:?}", Arcmo::<i32>::none()), "Arcmo(None)");
+        assert_eq!(v.modify(|_| format!("{:?}", v)), "Arcmo(<locked>)");
+    }
+
+    #[test]
+    fn test_weak_ptr_eq_and_expiry() {
+        let a = Arcmo::some(1);
+        let weak = a.downgrade();
+        assert!(weak.ptr_eq(&a.downgrade()));
+        assert!(!weak.ptr_eq(&Arcmo::some(1).downgrade()));
+
+        // An empty but live Arcmo isn't expired
+        a.clear();
+        assert!(!weak.is_expired());
+
+        drop(a);
+        assert!(weak.is_expired());
+    }
+
+    #[test]
+    fn test_with_upgraded() {
+        let session = Arcmo::some(String::from("token"));
+        let weak = session.downgrade();
+        let previous = weak.with_upgraded(|session| {
+            let previous = session.replace(String::from("rotated"));
+            assert!(session.contains(&String::from("rotated")));
+            previous
+        });
+        assert_eq!(previous, Some(Some(String::from("token"))));
+
+        drop(session);
+        assert_eq!(weak.with_upgraded(|s| s.is_some()), None);
+    }
+
+    #[test]
+    fn test_shares_with_and_refcounts() {
+        let a = Arcmo::some(vec![1]);
+        assert_eq!(a.debug_refcounts(), (1, 0));
+
+        let b = a.clone();
+        let weak = a.downgrade();
+        assert!(a.shares_with(&b));
+        assert_eq!(b.debug_refcounts(), (2, 1));
+
+        // Conversions produce independent storage
+        let copy = Arcmo::some(b.value().unwrap());
+        assert!(!copy.shares_with(&a));
+        let flat = Arcmo::some(Some(1)).flatten();
+        assert_eq!(flat.debug_refcounts(), (1, 0));
+
+        drop((b, weak));
+        assert_eq!(a.debug_refcounts(), (1, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_spawn_with() {
+        let slot = Arcmo::none();
+        slot.spawn_with(|s| s.set("ready")).join().unwrap();
+        assert_eq!(slot.value(), Some("ready"));
+    }
+
+    #[test]
+    fn test_cached_weak() {
+        let strong = Arcmo::some(1);
+        let mut cached = strong.downgrade().cached();
+        assert_eq!(cached.value(), Some(1));
+        assert_eq!(Arc::strong_count(&strong.inner), 2);
+
+        strong.set(2);
+        assert_eq!(cached.with(|v| v * 10), Some(20));
+        assert_eq!(Arc::strong_count(&strong.inner), 2);
+
+        drop(strong);
+        assert_eq!(cached.value(), None);
+        assert_eq!(cached.with(|v| *v), None);
+    }
+
+    #[test]
+    fn test_flatten() {
+        assert_eq!(Arcmo::some(Some(1)).flatten().value(), Some(1));
+        assert_eq!(Arcmo::some(None::<i32>).flatten().value(), None);
+        assert_eq!(Arcmo::<Option<i32>>::none().flatten().value(), None);
+
+        let nested = Arcmo::some(Some(1));
+        let flat = nested.clone().flatten();
+        flat.set(2);
+        assert_eq!(nested.value(), Some(Some(1)));
+    }
+
+    #[test]
+    fn test_weak_clone() {
+        let strong = Arcmo::some(1);
+        let weak = strong.downgrade();
+        let copy = weak.clone();
+        assert!(copy.ptr_eq(&weak));
+        assert_eq!(copy.value(), Some(1));
+        drop(strong);
+        assert!(copy.is_expired());
+    }
+
+    #[test]
+    fn test_prune_weak_arcmo() {
+        let live = Arcmo::<i32>::none();
+        let mut weaks = vec![live.downgrade(), Arcmo::some(1).downgrade()];
+        prune_weak_arcmo(&mut weaks);
+        assert_eq!(weaks.len(), 1);
+        assert!(weaks[0].ptr_eq(&live.downgrade()));
+    }
+
     #[test]
     fn test_weak_reference() {
         let strong = Arcmo::some(42);
@@ -421,6 +1914,30 @@ mod tests {
         assert_eq!(arcmo.value(), Some(200));
     }
 
+    #[test]
+    fn test_checked_methods() {
+        let arcmo = Arcmo::<i32>::none();
+        assert_eq!(arcmo.value_checked(), Ok(None));
+        assert_eq!(arcmo.try_modify_checked(|v| *v += 1), Ok(()));
+        assert_eq!(arcmo.replace_checked(100), Ok(Some(1)));
+
+        let arcmo_clone = arcmo.clone();
+        let _ = thread::spawn(move || {
+            arcmo_clone.modify(|_| panic!("Deliberate panic to poison mutex"));
+        })
+        .join();
+
+        // Only the std backend poisons
+        if cfg!(any(feature = "spin", feature = "parking_lot")) {
+            assert_eq!(arcmo.value_checked(), Ok(Some(100)));
+        } else {
+            assert_eq!(arcmo.value_checked(), Err(PoisonError));
+            assert_eq!(arcmo.try_modify_checked(|v| *v = 0), Err(PoisonError));
+            assert_eq!(arcmo.replace_checked(0), Err(PoisonError));
+            assert_eq!(arcmo.value(), Some(100));
+        }
+    }
+
     #[test]
     fn test_weak_arcmo_replace() {
         // Test with Some value
@@ -479,6 +1996,27 @@ mod tests {
         assert_eq!(strong.value(), Some(200));
     }
 
+    #[test]
+    fn test_weak_arcmo_is_poisoned() {
+        let strong = Arcmo::some(42);
+        let weak = strong.downgrade();
+        assert!(!strong.is_poisoned());
+        assert!(!weak.is_poisoned());
+
+        let strong_clone = strong.clone();
+        let _ = thread::spawn(move || strong_clone.modify(|_| panic!("poison"))).join();
+
+        // Recovery is silent, but still reported
+        let poisons = cfg!(not(any(feature = "spin", feature = "parking_lot")));
+        assert_eq!(weak.value(), Some(42));
+        assert_eq!(strong.is_poisoned(), poisons);
+        assert_eq!(weak.is_poisoned(), poisons);
+
+        // A weak handle whose Arcmo is gone reports false
+        drop(strong);
+        assert!(!weak.is_poisoned());
+    }
+
     #[test]
     fn test_weak_arcmo_none_to_some() {
         // Test upgrading None to Some via replace
@@ -495,4 +2033,14 @@ mod tests {
         assert!(weak.is_some());
         assert_eq!(strong.value(), Some(42));
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_force_poison() {
+        let poisons = cfg!(not(any(feature = "spin", feature = "parking_lot")));
+        let fresh = Arcmo::some(0);
+        fresh.force_poison();
+        assert_eq!(fresh.is_poisoned(), poisons);
+        assert_eq!(fresh.value(), Some(0));
+    }
 }
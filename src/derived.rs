@@ -0,0 +1,90 @@
+use crate::arcm::Arcm;
+use alloc::boxed::Box;
+use core::fmt::Debug;
+
+/// A value computed from another Arcm and kept up to date as it changes,
+/// returned by [`Arcm::derive`].
+///
+/// Updates stop when the Derived is dropped. Handles obtained through
+/// [`Derived::arcm`] keep the last value but no longer follow the source.
+pub struct Derived<U> {
+    target: Arcm<U>,
+    unsubscribe: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl<U> Derived<U> {
+    pub(crate) fn new(target: Arcm<U>, unsubscribe: Box<dyn FnOnce() + Send + Sync>) -> Self {
+        Self {
+            target,
+            unsubscribe: Some(unsubscribe),
+        }
+    }
+
+    /// Returns the Arcm holding the derived value, e.g. to subscribe to it or
+    /// derive from it in turn
+    pub fn arcm(&self) -> &Arcm<U> {
+        &self.target
+    }
+
+    /// Runs the provided closure with a reference to the derived value
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&U) -> R,
+    {
+        self.target.with(f)
+    }
+}
+
+impl<U: Clone> Derived<U> {
+    /// Returns a copy of the derived value
+    pub fn value(&self) -> U {
+        self.target.value()
+    }
+}
+
+impl<U> Drop for Derived<U> {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
+
+impl<U: Debug> Debug for Derived<U> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Derived")
+            .field("target", &self.target)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_follows_source() {
+        let celsius = Arcm::new(0.0);
+        let fahrenheit = celsius.derive(|c| c * 9.0 / 5.0 + 32.0);
+        assert_eq!(fahrenheit.value(), 32.0);
+
+        celsius.set(100.0);
+        assert_eq!(fahrenheit.value(), 212.0);
+
+        // Derived values can be derived from in turn
+        let label = fahrenheit.arcm().derive(|f| format!("{}°F", f));
+        celsius.set(-40.0);
+        assert_eq!(label.value(), "-40°F");
+    }
+
+    #[test]
+    fn test_drop_stops_updates() {
+        let source = Arcm::new(vec![1, 2, 3]);
+        let len = source.derive(|v| v.len());
+        let handle = len.arcm().clone();
+
+        drop(len);
+        source.modify(|v| v.push(4));
+        assert_eq!(handle.value(), 3);
+    }
+}
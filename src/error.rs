@@ -0,0 +1,66 @@
+//! Error types returned by the fallible wrapper methods.
+
+use core::fmt::{self, Display};
+
+/// Returned by the `*_checked` methods when a thread panicked while holding
+/// the lock, leaving the value in a possibly inconsistent state.
+///
+/// Only the default `std` backend can poison; with `spin` or `parking_lot`
+/// the checked methods always succeed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoisonError;
+
+impl Display for PoisonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lock poisoned by a thread that panicked while holding it")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PoisonError {}
+
+/// Returned by the lock methods that give up after a timeout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockError {
+    /// The lock was still held elsewhere when the timeout expired
+    Timeout,
+    /// The lock was poisoned; only reported by the `*_checked` variants
+    Poisoned,
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Timeout => f.write_str("timed out waiting for the lock"),
+            LockError::Poisoned => Display::fmt(&PoisonError, f),
+        }
+    }
+}
+
+/// Returned by `Arcm::with_timeout` when the lock couldn't be taken in time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&LockError::Timeout, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimedOut {}
+
+impl From<TimedOut> for LockError {
+    fn from(_: TimedOut) -> Self {
+        LockError::Timeout
+    }
+}
+
+impl From<PoisonError> for LockError {
+    fn from(_: PoisonError) -> Self {
+        LockError::Poisoned
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LockError {}
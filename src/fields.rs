@@ -0,0 +1,146 @@
+//! Per-field locking for structs whose fields are updated independently.
+//!
+//! A single `Arcm<Stats>` serializes every update, even when threads touch
+//! different fields. [`arcm_fields!`](crate::arcm_fields) instead generates a
+//! handle holding one Arcm per field, so updates to different fields don't
+//! contend, while `value()` still returns a consistent copy of the whole
+//! struct.
+
+use crate::arcm::Arcm;
+
+/// Used by [`arcm_fields!`](crate::arcm_fields) to lock fields in the same
+/// address order as the multi-handle functions
+#[doc(hidden)]
+pub fn addr<T>(arcm: &Arcm<T>) -> usize {
+    arcm.addr()
+}
+
+/// Generates a shared handle with one [`Arcm`] per field of a struct:
+/// `arcm_fields! { pub struct SharedStats(Stats) { hits: u64, misses: u64 } }`.
+///
+/// The generated type has `new`, which splits a value into its fields, one
+/// accessor per field returning its `&Arcm`, and `value`, which locks every
+/// field at once and reassembles a copy. Field types must implement `Clone`.
+/// Clones of the handle share the same fields.
+#[macro_export]
+macro_rules! arcm_fields {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($source:ident) {
+            $($field:ident: $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        $vis struct $name {
+            $($field: $crate::arcm::Arcm<$ty>,)+
+        }
+
+        impl $name {
+            /// Splits `value` into one Arcm per field
+            $vis fn new(value: $source) -> Self {
+                Self {
+                    $($field: $crate::arcm::Arcm::new(value.$field),)+
+                }
+            }
+
+            $(
+                /// Returns the Arcm holding this field
+                $vis fn $field(&self) -> &$crate::arcm::Arcm<$ty> {
+                    &self.$field
+                }
+            )+
+
+            /// Returns a copy of the whole struct. Every field is locked
+            /// before any is read, so the copy is consistent.
+            $vis fn value(&self) -> $source {
+                $(let mut $field = None;)+
+                let mut order = [$($crate::fields::addr(&self.$field),)+];
+                order.sort_unstable();
+                for addr in order {
+                    $(
+                        if addr == $crate::fields::addr(&self.$field) {
+                            $field = Some(self.$field.lock());
+                        }
+                    )+
+                }
+                $source {
+                    $($field: ::core::clone::Clone::clone(&*$field.unwrap()),)+
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::with_both;
+    use std::thread;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Stats {
+        hits: u64,
+        misses: u64,
+        last: String,
+    }
+
+    arcm_fields! {
+        /// Stats with independently locked fields
+        struct SharedStats(Stats) {
+            hits: u64,
+            misses: u64,
+            last: String,
+        }
+    }
+
+    #[test]
+    fn test_fields_lock_independently() {
+        let stats = SharedStats::new(Stats {
+            hits: 0,
+            misses: 0,
+            last: String::new(),
+        });
+
+        let held = stats.hits().lock();
+        let other = stats.clone();
+        thread::spawn(move || other.misses().modify(|n| *n += 1))
+            .join()
+            .unwrap();
+        drop(held);
+
+        stats.hits().modify(|n| *n += 2);
+        stats.last().set(String::from("miss"));
+        assert_eq!(
+            stats.value(),
+            Stats {
+                hits: 2,
+                misses: 1,
+                last: String::from("miss"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_value_alongside_pair_locks() {
+        let stats = SharedStats::new(Stats {
+            hits: 0,
+            misses: 0,
+            last: String::new(),
+        });
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let stats = stats.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        with_both(stats.misses(), stats.hits(), |_, _| ());
+                        stats.value();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
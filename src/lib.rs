@@ -1,2 +1,75 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "spin")))]
+compile_error!("sovran-arc requires either the `std` or the `spin` feature");
+
+#[cfg(all(feature = "spin", feature = "parking_lot"))]
+compile_error!(
+    "the `spin` and `parking_lot` features select different mutex backends and cannot be combined"
+);
+
 pub mod arcm;
+#[cfg(feature = "tokio")]
+pub mod arcm_async;
+pub mod arcm_atomic;
+#[cfg(feature = "std")]
+pub mod arcm_cell;
+pub mod arcm_deque;
+pub mod arcm_dyn;
+#[cfg(feature = "std")]
+pub mod arcm_map;
+pub mod arcm_vec;
 pub mod arcmo;
+pub mod derived;
+pub mod error;
+pub mod fields;
+pub mod memo;
+mod multi;
+pub mod projection;
+mod sync;
+
+pub use arcm::prune_weak;
+pub use arcmo::prune_weak_arcmo;
+pub use multi::{fold_values, modify_all, with_both, zip};
+
+/// The mutex behind every handle, exposed for the interop APIs
+/// ([`Arcm::from_arc`](arcm::Arcm::from_arc),
+/// [`Arcm::as_arc`](arcm::Arcm::as_arc) and
+/// [`Arcm::project_weak`](arcm::Arcm::project_weak)).
+///
+/// Which type this is depends on the enabled features: `std::sync::Mutex` by
+/// default, `parking_lot::Mutex` with `parking_lot` or `fair`, and
+/// `spin::Mutex` with `spin`. Since features are unified across the dependency
+/// graph, code that builds these `Arc`s should name this alias rather than a
+/// specific mutex.
+pub type Mutex<T> = sync::Mutex<T>;
+
+// Every handle is Send + Sync exactly when its contents are Send, like
+// Arc<Mutex<T>>. Checked here so a change to the internals can't quietly
+// tie them to one thread.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn handles<T: Send>() {
+        assert_send_sync::<arcm::Arcm<T>>();
+        assert_send_sync::<arcm::WeakArcm<T>>();
+        assert_send_sync::<arcmo::Arcmo<T>>();
+        assert_send_sync::<arcmo::WeakArcmo<T>>();
+        assert_send_sync::<arcmo::CachedWeakArcmo<T>>();
+        assert_send_sync::<derived::Derived<T>>();
+        assert_send_sync::<memo::Memo<T>>();
+        assert_send_sync::<projection::WeakProjection<T>>();
+        #[cfg(feature = "std")]
+        assert_send_sync::<arcm_cell::ArcmCell<T>>();
+        #[cfg(feature = "tokio")]
+        assert_send_sync::<arcm_async::ArcmAsync<T>>();
+    }
+    fn unsized_handles<T: ?Sized + Send>() {
+        assert_send_sync::<arcm_dyn::ArcmDyn<T>>();
+    }
+    assert_send_sync::<arcm_atomic::ArcmAtomic<usize>>();
+    assert_send_sync::<arcm_atomic::WeakArcmAtomic<bool>>();
+    handles::<()>();
+    unsized_handles::<dyn Send>();
+};
@@ -0,0 +1,106 @@
+use crate::arcmo::Arcmo;
+use core::fmt::Debug;
+
+/// A shared, lazily computed value. Clones share the same slot, and the
+/// computation runs at most once to completion no matter how many threads
+/// ask for it at the same time.
+pub struct Memo<T> {
+    inner: Arcmo<T>,
+}
+
+impl<T> Memo<T> {
+    /// Creates an empty Memo
+    pub fn new() -> Self {
+        Self {
+            inner: Arcmo::none(),
+        }
+    }
+
+    /// Returns true once the value has been computed
+    pub fn is_computed(&self) -> bool {
+        self.inner.is_some()
+    }
+}
+
+impl<T: Clone> Memo<T> {
+    /// Returns a copy of the value, computing it with `f` first if this is the
+    /// first call.
+    ///
+    /// `f` runs under the lock, so threads racing the first call wait for it
+    /// rather than computing the value again. It must not use this Memo. If
+    /// `f` panics nothing is stored, and the next call computes it again.
+    pub fn get_or_compute<F>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.inner.entry(f, |value| value.clone())
+    }
+
+    /// Returns a copy of the value if it has been computed
+    pub fn get(&self) -> Option<T> {
+        self.inner.value()
+    }
+}
+
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Default for Memo<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for Memo<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Memo").field("inner", &self.inner).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_get_or_compute() {
+        let memo = Memo::new();
+        assert!(!memo.is_computed());
+        assert_eq!(memo.get(), None);
+
+        assert_eq!(memo.get_or_compute(|| 42), 42);
+        assert_eq!(memo.get_or_compute(|| panic!("computed twice")), 42);
+        assert!(memo.clone().is_computed());
+        assert_eq!(memo.get(), Some(42));
+    }
+
+    #[test]
+    fn test_computes_once_under_contention() {
+        let memo = Memo::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (memo, calls) = (memo.clone(), Arc::clone(&calls));
+                thread::spawn(move || {
+                    memo.get_or_compute(|| {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        thread::yield_now();
+                        String::from("expensive")
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "expensive");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,267 @@
+//! Operations that lock several handles at once.
+//!
+//! Locks are always acquired in order of the handles' allocation addresses, so
+//! two threads touching overlapping sets of handles in different orders can't
+//! deadlock each other.
+
+use crate::arcm::Arcm;
+use crate::arcmo::Arcmo;
+use crate::sync::lock_pair;
+use alloc::vec::Vec;
+
+/// Locks every handle and runs `f` with mutable access to all of their values
+/// at once, in the same order as `handles`.
+///
+/// # Panics
+///
+/// Panics if two of the handles share the same allocation, since that would
+/// hand out two mutable references to the same value.
+pub fn modify_all<T, F, R>(handles: &[&Arcm<T>], f: F) -> R
+where
+    F: FnOnce(&mut [&mut T]) -> R,
+{
+    let mut order: Vec<usize> = (0..handles.len()).collect();
+    order.sort_by_key(|&i| handles[i].addr());
+    assert!(
+        order
+            .windows(2)
+            .all(|pair| handles[pair[0]].addr() != handles[pair[1]].addr()),
+        "modify_all called with the same Arcm more than once"
+    );
+
+    let mut guards: Vec<_> = handles.iter().map(|_| None).collect();
+    for i in order {
+        guards[i] = Some(handles[i].guard());
+    }
+    let mut guards: Vec<_> = guards.into_iter().flatten().collect();
+
+    let result = {
+        let mut values: Vec<&mut T> = guards.iter_mut().map(|guard| &mut **guard).collect();
+        f(&mut values)
+    };
+    for (handle, guard) in handles.iter().zip(&guards) {
+        handle.changed(guard);
+    }
+    result
+}
+
+/// Locks both handles and runs `f` with references to both values, so it
+/// sees a consistent view of the two.
+///
+/// # Panics
+///
+/// Panics if both handles share the same allocation.
+pub fn with_both<A, B, F, R>(a: &Arcm<A>, b: &Arcm<B>, f: F) -> R
+where
+    F: FnOnce(&A, &B) -> R,
+{
+    assert!(
+        a.addr() != b.addr(),
+        "with_both called with the same Arcm twice"
+    );
+    let (a, b) = if a.addr() < b.addr() {
+        let a = a.guard();
+        (a, b.guard())
+    } else {
+        let b = b.guard();
+        (a.guard(), b)
+    };
+    f(&a, &b)
+}
+
+/// Folds every handle's value into an accumulator, in the order of
+/// `handles`, while all of them are locked, so the result reflects one
+/// consistent moment. Handles that share an allocation are locked once and
+/// their value is visited once per occurrence.
+pub fn fold_values<T, A, F>(handles: &[Arcm<T>], init: A, mut f: F) -> A
+where
+    F: FnMut(A, &T) -> A,
+{
+    let mut order: Vec<usize> = (0..handles.len()).collect();
+    order.sort_by_key(|&i| handles[i].addr());
+
+    // `source[i]` is the position whose guard holds handle i's value
+    let mut guards: Vec<_> = handles.iter().map(|_| None).collect();
+    let mut source: Vec<usize> = (0..handles.len()).collect();
+    let mut previous: Option<usize> = None;
+    for i in order {
+        match previous {
+            Some(p) if handles[p].addr() == handles[i].addr() => source[i] = source[p],
+            _ => guards[i] = Some(handles[i].guard()),
+        }
+        previous = Some(i);
+    }
+
+    source.iter().fold(init, |acc, &i| match &guards[i] {
+        Some(guard) => f(acc, guard),
+        None => unreachable!("every allocation is locked once"),
+    })
+}
+
+/// Reads two optional values under a single pair of locks, returning copies
+/// of both only if both are present.
+///
+/// # Panics
+///
+/// Panics if both handles share the same allocation.
+pub fn zip<A, B>(a: &Arcmo<A>, b: &Arcmo<B>) -> Option<(A, B)>
+where
+    A: Clone,
+    B: Clone,
+{
+    let (a, b) = (a.mutex(), b.mutex());
+    assert!(
+        !core::ptr::eq(a as *const _ as *const u8, b as *const _ as *const u8),
+        "zip called with the same Arcmo twice"
+    );
+    let (a, b) = lock_pair(a, b);
+    Some((a.as_ref()?.clone(), b.as_ref()?.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Account {
+        balance: i64,
+    }
+
+    fn transfer(from: &Arcm<Account>, to: &Arcm<Account>, amount: i64) {
+        modify_all(&[from, to], |accounts| {
+            accounts[0].balance -= amount;
+            accounts[1].balance += amount;
+        });
+    }
+
+    #[test]
+    fn test_modify_all() {
+        let a = Arcm::new(Account { balance: 100 });
+        let b = Arcm::new(Account { balance: 0 });
+
+        transfer(&a, &b, 30);
+        assert_eq!(a.value().balance, 70);
+        assert_eq!(b.value().balance, 30);
+
+        // Values are handed out in the caller's order regardless of lock order
+        let total = modify_all(&[&b, &a], |accounts| {
+            assert_eq!(accounts[0].balance, 30);
+            accounts.iter().map(|a| a.balance).sum::<i64>()
+        });
+        assert_eq!(total, 100);
+        assert_eq!(a.generation(), 2);
+    }
+
+    #[test]
+    fn test_modify_all_opposite_orders() {
+        let a = Arcm::new(Account { balance: 1000 });
+        let b = Arcm::new(Account { balance: 1000 });
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let (a, b) = (a.clone(), b.clone());
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        if i % 2 == 0 {
+                            transfer(&a, &b, 1);
+                        } else {
+                            transfer(&b, &a, 1);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(a.value().balance + b.value().balance, 2000);
+    }
+
+    #[test]
+    #[should_panic(expected = "same Arcm more than once")]
+    fn test_modify_all_rejects_aliases() {
+        let a = Arcm::new(Account { balance: 0 });
+        let alias = a.clone();
+        modify_all(&[&a, &alias], |_| ());
+    }
+
+    #[test]
+    fn test_with_both() {
+        let a = Arcm::new(Account { balance: 100 });
+        let b = Arcm::new(Account { balance: 0 });
+
+        let mover = {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    transfer(&a, &b, 1);
+                    transfer(&b, &a, 1);
+                }
+            })
+        };
+        for _ in 0..200 {
+            let total = with_both(&a, &b, |a, b| a.balance + b.balance);
+            assert_eq!(total, 100);
+        }
+        mover.join().unwrap();
+
+        let label = Arcm::new("total");
+        assert_eq!(
+            with_both(&label, &a, |l, a| (*l, a.balance)),
+            ("total", 100)
+        );
+    }
+
+    #[test]
+    fn test_fold_values() {
+        let counters: Vec<_> = (1..=4).map(Arcm::new).collect();
+        assert_eq!(fold_values(&counters, 0, |sum, n| sum + n), 10);
+        assert_eq!(fold_values(&[] as &[Arcm<i32>], 7, |sum, n| sum + n), 7);
+
+        // Aliases are locked once but counted at each position
+        let shared = Arcm::new(5);
+        let handles = [shared.clone(), Arcm::new(1), shared];
+        let seen = fold_values(&handles, Vec::new(), |mut seen, n| {
+            seen.push(*n);
+            seen
+        });
+        assert_eq!(seen, vec![5, 1, 5]);
+    }
+
+    #[test]
+    fn test_fold_values_consistent() {
+        let a = Arcm::new(Account { balance: 100 });
+        let b = Arcm::new(Account { balance: 0 });
+        let mover = {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    transfer(&b, &a, 1);
+                    transfer(&a, &b, 1);
+                }
+            })
+        };
+        for _ in 0..200 {
+            let handles = [b.clone(), a.clone()];
+            let total = fold_values(&handles, 0, |sum, acct| sum + acct.balance);
+            assert_eq!(total, 100);
+        }
+        mover.join().unwrap();
+    }
+
+    #[test]
+    fn test_zip() {
+        let name = Arcmo::some("alice".to_string());
+        let age = Arcmo::<u32>::none();
+        assert_eq!(zip(&name, &age), None);
+
+        age.set(30);
+        assert_eq!(zip(&name, &age), Some(("alice".to_string(), 30)));
+        assert_eq!(zip(&age, &name), Some((30, "alice".to_string())));
+
+        name.take();
+        assert_eq!(zip(&name, &age), None);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::arcm::WeakArcm;
+use crate::sync::{lock, Mutex};
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+type Resolve<U> = dyn Fn() -> Option<Arc<Mutex<U>>> + Send + Sync;
+type Expired = dyn Fn() -> bool + Send + Sync;
+
+/// A weak handle to a shared value nested inside an Arcm, returned by
+/// [`Arcm::project_weak`](crate::arcm::Arcm::project_weak).
+///
+/// Each access goes through the parent: it upgrades the parent, briefly locks
+/// it to find the nested `Arc`, then releases it before locking the child. So
+/// the projection follows the parent if the child is swapped out, and stops
+/// working once every strong parent handle is gone, even if the child itself
+/// is still alive elsewhere.
+pub struct WeakProjection<U> {
+    resolve: Arc<Resolve<U>>,
+    expired: Arc<Expired>,
+}
+
+impl<U> WeakProjection<U> {
+    pub(crate) fn new<T, F>(parent: WeakArcm<T>, f: F) -> Self
+    where
+        T: Send + 'static,
+        F: Fn(&T) -> &Arc<Mutex<U>> + Send + Sync + 'static,
+    {
+        let liveness = parent.clone();
+        Self {
+            resolve: Arc::new(move || Some(parent.upgrade()?.with(|value| Arc::clone(f(value))))),
+            expired: Arc::new(move || liveness.is_expired()),
+        }
+    }
+
+    /// Modifies the nested value if the parent still exists
+    pub fn modify<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut U) -> R,
+    {
+        let child = (self.resolve)()?;
+        let result = f(&mut lock(&child));
+        Some(result)
+    }
+
+    /// Runs `f` with a reference to the nested value if the parent still
+    /// exists
+    pub fn with<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&U) -> R,
+    {
+        let child = (self.resolve)()?;
+        let result = f(&lock(&child));
+        Some(result)
+    }
+
+    /// Returns true once every strong handle to the parent has been dropped,
+    /// without upgrading or locking it
+    pub fn is_expired(&self) -> bool {
+        (self.expired)()
+    }
+}
+
+impl<U: Clone> WeakProjection<U> {
+    /// Returns a copy of the nested value if the parent still exists
+    pub fn value(&self) -> Option<U> {
+        self.with(U::clone)
+    }
+}
+
+impl<U> Clone for WeakProjection<U> {
+    fn clone(&self) -> Self {
+        Self {
+            resolve: Arc::clone(&self.resolve),
+            expired: Arc::clone(&self.expired),
+        }
+    }
+}
+
+impl<U> Debug for WeakProjection<U> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WeakProjection").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arcm::Arcm;
+    use crate::sync::lock;
+    use crate::Mutex;
+    use alloc::sync::Arc;
+
+    struct Model {
+        title: String,
+        selection: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[test]
+    fn test_project_weak() {
+        let model = Arcm::new(Model {
+            title: String::from("doc"),
+            selection: Arc::new(Mutex::new(vec![1])),
+        });
+        let selection = model.project_weak(|m| &m.selection);
+
+        selection.modify(|s| s.push(2));
+        assert_eq!(selection.value(), Some(vec![1, 2]));
+        assert_eq!(model.with(|m| lock(&m.selection).len()), 2);
+
+        // Follows the parent when the nested Arc is replaced
+        model.modify(|m| m.selection = Arc::new(Mutex::new(Vec::new())));
+        assert_eq!(selection.with(|s| s.len()), Some(0));
+        assert_eq!(model.with(|m| m.title.clone()), "doc");
+
+        // Checking for expiry doesn't lock the parent
+        let held = model.lock();
+        assert!(!selection.is_expired());
+        drop(held);
+
+        // Expires with the parent even if the child is still shared
+        let child = model.with(|m| Arc::clone(&m.selection));
+        drop(model);
+        assert!(selection.is_expired());
+        assert_eq!(selection.clone().value(), None);
+        assert_eq!(Arc::strong_count(&child), 1);
+    }
+}
@@ -0,0 +1,477 @@
+//! Mutex backend shared by the wrapper types.
+//!
+//! With the default `std` feature this is `std::sync::Mutex`, and poisoning is
+//! recovered from transparently. The `parking_lot` feature swaps in
+//! `parking_lot::Mutex`, and the `spin` feature builds against `alloc` and
+//! `spin::Mutex` for `no_std` targets. Neither of those ever poisons. The
+//! `fair` feature builds on `parking_lot` and releases every guard with
+//! `unlock_fair`, handing the lock to waiting threads in the order they queued.
+
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+pub(crate) use parking_lot::{Condvar, Mutex};
+#[cfg(all(feature = "parking_lot", not(any(feature = "spin", feature = "fair"))))]
+pub(crate) use parking_lot::{Condvar, Mutex, MutexGuard};
+#[cfg(feature = "spin")]
+pub(crate) use spin::{Mutex, MutexGuard};
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) use std::sync::{Condvar, Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+use crate::error::LockError;
+use crate::error::PoisonError;
+#[cfg(all(debug_assertions, feature = "std"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Locks the mutex, recovering the guard if a previous holder panicked
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Locks the mutex; spin and parking_lot mutexes never poison so there is
+/// nothing to recover
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    wrap(mutex.lock())
+}
+
+/// A parking_lot guard that unlocks fairly when dropped
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+pub(crate) struct MutexGuard<'a, T: ?Sized>(
+    core::mem::ManuallyDrop<parking_lot::MutexGuard<'a, T>>,
+);
+
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+impl<T: ?Sized> core::ops::Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+impl<T: ?Sized> core::ops::DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the inner guard is taken exactly once, here, and never
+        // touched again
+        let guard = unsafe { core::mem::ManuallyDrop::take(&mut self.0) };
+        parking_lot::MutexGuard::unlock_fair(guard);
+    }
+}
+
+/// Wraps a backend guard so it unlocks fairly
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+fn wrap<T: ?Sized>(guard: parking_lot::MutexGuard<'_, T>) -> MutexGuard<'_, T> {
+    MutexGuard(core::mem::ManuallyDrop::new(guard))
+}
+
+/// Backend guards are used as they are unless the `fair` feature is on
+#[cfg(all(
+    any(feature = "spin", feature = "parking_lot"),
+    not(all(feature = "fair", not(feature = "spin")))
+))]
+fn wrap<G>(guard: G) -> G {
+    guard
+}
+
+/// Returns true if a previous holder panicked while holding the lock. Stays
+/// true after recovery, since recovering doesn't clear the poison flag.
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn is_poisoned<T: ?Sized>(mutex: &Mutex<T>) -> bool {
+    mutex.is_poisoned()
+}
+
+/// Spin and parking_lot mutexes never poison
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn is_poisoned<T: ?Sized>(_mutex: &Mutex<T>) -> bool {
+    false
+}
+
+/// Locks the mutex if it is free, recovering the guard if a previous holder
+/// panicked. Returns None if another holder has it.
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn try_lock<T: ?Sized>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Locks the mutex if it is free. Returns None if another holder has it.
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn try_lock<T: ?Sized>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    mutex.try_lock().map(wrap)
+}
+
+/// Locks the mutex if it is free, reporting poisoning instead of recovering
+/// from it. Returns `Ok(None)` if another holder has it.
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn try_lock_checked<T: ?Sized>(
+    mutex: &Mutex<T>,
+) -> Result<Option<MutexGuard<'_, T>>, PoisonError> {
+    match mutex.try_lock() {
+        Ok(guard) => Ok(Some(guard)),
+        Err(std::sync::TryLockError::Poisoned(_)) => Err(PoisonError),
+        Err(std::sync::TryLockError::WouldBlock) => Ok(None),
+    }
+}
+
+/// Locks the mutex if it is free; without poisoning this never fails
+#[cfg(all(feature = "std", any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn try_lock_checked<T: ?Sized>(
+    mutex: &Mutex<T>,
+) -> Result<Option<MutexGuard<'_, T>>, PoisonError> {
+    Ok(mutex.try_lock().map(wrap))
+}
+
+/// Longest pause between attempts in [`lock_timeout`]
+#[cfg(feature = "std")]
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_millis(1);
+
+/// Repeats `attempt` with exponential backoff until it returns a guard, fails,
+/// or `timeout` has passed
+#[cfg(feature = "std")]
+pub(crate) fn lock_timeout<G, F>(
+    timeout: std::time::Duration,
+    mut attempt: F,
+) -> Result<G, LockError>
+where
+    F: FnMut() -> Result<Option<G>, PoisonError>,
+{
+    let deadline = std::time::Instant::now() + timeout;
+    let mut backoff = std::time::Duration::from_micros(1);
+    loop {
+        if let Some(guard) = attempt()? {
+            return Ok(guard);
+        }
+        let now = std::time::Instant::now();
+        if now >= deadline {
+            return Err(LockError::Timeout);
+        }
+        std::thread::sleep(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Poisons the mutex by panicking in a scoped thread while holding it. Only
+/// the `std` backend poisons; the others simply release the lock.
+#[cfg(all(feature = "std", any(test, feature = "test-util")))]
+pub(crate) fn force_poison<T: ?Sized + Send>(mutex: &Mutex<T>) {
+    std::thread::scope(|scope| {
+        let _ = scope
+            .spawn(|| {
+                let _guard = lock(mutex);
+                panic!("mutex poisoned on purpose by force_poison");
+            })
+            .join();
+    });
+}
+
+/// Polls `done` with the same backoff as [`lock_timeout`] until it returns
+/// true, or until `timeout` has passed if one is given. Returns the last
+/// result of `done`.
+#[cfg(feature = "std")]
+pub(crate) fn poll_until<F>(timeout: Option<std::time::Duration>, mut done: F) -> bool
+where
+    F: FnMut() -> bool,
+{
+    let deadline = timeout.and_then(|timeout| std::time::Instant::now().checked_add(timeout));
+    let mut backoff = std::time::Duration::from_micros(1);
+    loop {
+        if done() {
+            return true;
+        }
+        let pause = match deadline {
+            Some(deadline) => {
+                let now = std::time::Instant::now();
+                if now >= deadline {
+                    return false;
+                }
+                backoff.min(deadline - now)
+            }
+            None => backoff,
+        };
+        std::thread::sleep(pause);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Span kept entered while an instrumented operation holds the lock. A unit
+/// type without the `tracing` feature, so instrumentation costs nothing there.
+#[cfg(feature = "tracing")]
+pub(crate) type Span = tracing::span::EnteredSpan;
+#[cfg(not(feature = "tracing"))]
+pub(crate) type Span = ();
+
+/// Locks the mutex inside a trace-level span for `op` on a `T`, recording
+/// whether the lock was contended. Contention is detected by a failed
+/// `try_lock` before falling back to blocking.
+#[cfg(feature = "tracing")]
+pub(crate) fn lock_traced<'a, T>(
+    mutex: &'a Mutex<T>,
+    op: &'static str,
+) -> (Span, MutexGuard<'a, T>) {
+    let span = tracing::trace_span!(
+        "sovran_arc::lock",
+        op,
+        type_name = core::any::type_name::<T>(),
+        contended = tracing::field::Empty,
+    )
+    .entered();
+    let guard = match try_lock(mutex) {
+        Some(guard) => {
+            span.record("contended", false);
+            guard
+        }
+        None => {
+            span.record("contended", true);
+            lock(mutex)
+        }
+    };
+    (span, guard)
+}
+
+/// Locks the mutex; without the `tracing` feature this is just [`lock`]
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn lock_traced<'a, T>(
+    mutex: &'a Mutex<T>,
+    _op: &'static str,
+) -> (Span, MutexGuard<'a, T>) {
+    ((), lock(mutex))
+}
+
+/// Formats the value as `name(value)` without blocking: `<locked>` stands in
+/// for the value while another holder (or this thread) has the lock, and a
+/// `<poisoned>` marker is added if a holder panicked
+pub(crate) fn fmt_debug<T: ?Sized + core::fmt::Debug>(
+    f: &mut core::fmt::Formatter<'_>,
+    name: &str,
+    mutex: &Mutex<T>,
+) -> core::fmt::Result {
+    let mut tuple = f.debug_tuple(name);
+    match try_lock(mutex) {
+        Some(value) => tuple.field(&&*value),
+        None => tuple.field(&format_args!("<locked>")),
+    };
+    if is_poisoned(mutex) {
+        tuple.field(&format_args!("<poisoned>"));
+    }
+    tuple.finish()
+}
+
+/// Consumes the mutex and returns its value, recovering from poisoning
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+    mutex
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Consumes the mutex and returns its value
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+    mutex.into_inner()
+}
+
+/// Borrows the value of an exclusively owned mutex, recovering from poisoning
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn get_mut<T: ?Sized>(mutex: &mut Mutex<T>) -> &mut T {
+    mutex
+        .get_mut()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Borrows the value of an exclusively owned mutex
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn get_mut<T: ?Sized>(mutex: &mut Mutex<T>) -> &mut T {
+    mutex.get_mut()
+}
+
+/// Blocks on the condvar while `condition` holds, recovering from poisoning
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn wait_while<'a, T, F>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, T>,
+    condition: F,
+) -> MutexGuard<'a, T>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    condvar
+        .wait_while(guard, condition)
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Blocks on the condvar while `condition` holds
+#[cfg(all(feature = "parking_lot", not(any(feature = "spin", feature = "fair"))))]
+pub(crate) fn wait_while<'a, T, F>(
+    condvar: &Condvar,
+    mut guard: MutexGuard<'a, T>,
+    condition: F,
+) -> MutexGuard<'a, T>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    condvar.wait_while(&mut guard, condition);
+    guard
+}
+
+/// Blocks on the condvar while `condition` holds
+#[cfg(all(feature = "fair", not(feature = "spin")))]
+pub(crate) fn wait_while<'a, T, F>(
+    condvar: &Condvar,
+    mut guard: MutexGuard<'a, T>,
+    condition: F,
+) -> MutexGuard<'a, T>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    condvar.wait_while(&mut guard.0, condition);
+    guard
+}
+
+/// Locks two distinct mutexes in address order, so concurrent calls with the
+/// arguments swapped can't deadlock. The guards are returned in argument order.
+pub(crate) fn lock_pair<'a, A, B>(
+    a: &'a Mutex<A>,
+    b: &'a Mutex<B>,
+) -> (MutexGuard<'a, A>, MutexGuard<'a, B>) {
+    if (a as *const Mutex<A> as usize) < (b as *const Mutex<B> as usize) {
+        let a = lock(a);
+        (a, lock(b))
+    } else {
+        let b = lock(b);
+        (lock(a), b)
+    }
+}
+
+/// Locks the mutex, reporting poisoning instead of recovering from it
+#[cfg(not(any(feature = "spin", feature = "parking_lot")))]
+pub(crate) fn lock_checked<T: ?Sized>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, PoisonError> {
+    mutex.lock().map_err(|_| PoisonError)
+}
+
+/// Locks the mutex; without poisoning this always succeeds
+#[cfg(any(feature = "spin", feature = "parking_lot"))]
+pub(crate) fn lock_checked<T: ?Sized>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, PoisonError> {
+    Ok(wrap(mutex.lock()))
+}
+
+/// Records which thread holds a lock so that locking it again from the same
+/// thread panics instead of deadlocking. Only active in debug builds with
+/// `std`; otherwise it compiles to nothing.
+pub(crate) struct Owner {
+    #[cfg(all(debug_assertions, feature = "std"))]
+    thread: AtomicUsize,
+}
+
+/// Clears the recorded owner when dropped, which must happen before the
+/// corresponding guard unlocks
+pub(crate) struct Held<'a> {
+    #[cfg(all(debug_assertions, feature = "std"))]
+    owner: &'a Owner,
+    #[cfg(not(all(debug_assertions, feature = "std")))]
+    owner: core::marker::PhantomData<&'a Owner>,
+}
+
+#[cfg(all(debug_assertions, feature = "std"))]
+impl Owner {
+    pub(crate) const fn new() -> Self {
+        Self {
+            thread: AtomicUsize::new(0),
+        }
+    }
+
+    /// Panics if the current thread already holds the lock
+    pub(crate) fn check(&self, type_name: &str) {
+        if self.thread.load(Ordering::Relaxed) == current_thread() {
+            panic!(
+                "reentrant lock on {} detected: this thread already holds the lock",
+                type_name
+            );
+        }
+    }
+
+    /// Records the current thread as the holder, called once the lock is taken
+    pub(crate) fn enter(&self) -> Held<'_> {
+        self.thread.store(current_thread(), Ordering::Relaxed);
+        Held { owner: self }
+    }
+
+    /// Re-records the current thread after reacquiring the lock when waking
+    /// from a condvar
+    #[cfg(not(feature = "spin"))]
+    pub(crate) fn claim(&self) {
+        self.thread.store(current_thread(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(all(debug_assertions, feature = "std")))]
+impl Owner {
+    pub(crate) const fn new() -> Self {
+        Self {}
+    }
+
+    pub(crate) fn check(&self, _type_name: &str) {}
+
+    pub(crate) fn enter(&self) -> Held<'_> {
+        Held {
+            owner: core::marker::PhantomData,
+        }
+    }
+
+    #[cfg(not(feature = "spin"))]
+    pub(crate) fn claim(&self) {}
+}
+
+#[cfg(all(debug_assertions, feature = "std"))]
+impl Drop for Held<'_> {
+    fn drop(&mut self) {
+        self.owner.thread.store(0, Ordering::Relaxed);
+    }
+}
+
+/// A per-thread identifier: the address of a thread local, which is unique
+/// among live threads and never zero
+#[cfg(all(debug_assertions, feature = "std"))]
+fn current_thread() -> usize {
+    std::thread_local!(static MARKER: u8 = const { 0 });
+    MARKER.with(|marker| marker as *const u8 as usize)
+}
+
+#[cfg(target_has_atomic = "64")]
+type GenerationAtomic = core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+type GenerationAtomic = core::sync::atomic::AtomicUsize;
+
+/// Modification counter behind the `generation` methods. 64 bits wide where
+/// the target has 64-bit atomics, pointer-sized (and so wrapping sooner)
+/// elsewhere.
+pub(crate) struct Generation(GenerationAtomic);
+
+impl Generation {
+    pub(crate) const fn new() -> Self {
+        Self(GenerationAtomic::new(0))
+    }
+
+    // A no-op cast where the counter is already a u64
+    #[allow(clippy::unnecessary_cast)]
+    pub(crate) fn get(&self) -> u64 {
+        self.0.load(core::sync::atomic::Ordering::Acquire) as u64
+    }
+
+    pub(crate) fn bump(&self) {
+        self.0.fetch_add(1, core::sync::atomic::Ordering::Release);
+    }
+}